@@ -2,14 +2,43 @@
 use anchor_lang::prelude::*;
 
 // 导入 invoke_signed 函数，用于调用需要签名的指令
-use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
 
 // 导入系统程序模块，用于账户创建和转账等操作
 use anchor_lang::system_program;
 
+// 导入系统指令构造函数，用于构造转账提案
+use anchor_lang::solana_program::system_instruction;
+
+// 导入哈希函数，用于校验交易缓冲区内容
+use anchor_lang::solana_program::hash::hash;
+
+// 导入关联代币账户模块，用于构造代币转账提案和创建金库代币账户
+use anchor_spl::associated_token::{get_associated_token_address_with_program_id, AssociatedToken};
+
+// 导入 Memo 程序模块，用于向要求备注的 Token-2022 账户转账
+use anchor_spl::memo::{self, BuildMemo, Memo};
+
+// 导入 Token-2022 扩展模块，用于处理转账手续费和转入备注要求
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    memo_transfer::memo_required, transfer_fee::TransferFeeConfig, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Account as TokenAccountState;
+// 导入 Token-2022 的指令枚举，用于识别代币转账指令
+use anchor_spl::token_2022::spl_token_2022::instruction::TokenInstruction;
+
+// 导入 Token 与 Token-2022 程序模块，用于识别 mint 所属的代币程序
+use anchor_spl::{token, token_2022};
+
+// 导入代币接口模块，同时支持 Token 与 Token-2022 程序的代币转账
+use anchor_spl::token_interface::{
+    self, get_mint_extension_data, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked, TransferCheckedWithFee,
+};
+
 // 导入 HashSet，用于高效检查 owner 列表中的重复公钥
 use std::collections::HashSet;
-use std::ops::Mul;
+use std::ops::Range;
 // ENHANCEMENT: For efficient owner lookups
 
 // 定义常量：多签账户支持的最大 owner 数量
@@ -27,6 +56,63 @@ const MAX_IX_DATA_LEN: usize = 256;
 // 定义常量：白名单支持的最大程序数量
 const MAX_WHITELIST_SIZE: usize = 20;
 
+// 定义常量：地址簿支持的最大地址数量
+const MAX_ADDRESS_BOOK_SIZE: usize = 32;
+
+// 定义常量：每个支出限额允许的最大目标地址数量
+const MAX_SPENDING_LIMIT_DESTINATIONS: usize = 10;
+
+// 定义常量：每个白名单程序允许的最大指令前缀数量
+const MAX_PREFIXES_PER_PROGRAM: usize = 8;
+
+// 定义常量：指令前缀的最大长度（字节），足以容纳 Anchor 的 8 字节 discriminator
+const MAX_PREFIX_LEN: usize = 8;
+
+// 定义常量：单个交易可声明的最大临时签名者数量
+const MAX_EPHEMERAL_SIGNERS: usize = 4;
+
+// 定义常量：单个提案可引用的最大地址查找表数量
+const MAX_ADDRESS_TABLE_LOOKUPS: usize = 4;
+
+// 定义常量：地址查找表账户数据中元数据部分的长度，其后依次存放 32 字节的地址
+const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+// 定义常量：地址查找表程序 ID
+const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey =
+    pubkey!("AddressLookupTab1e1111111111111111111111111");
+
+// 定义常量：交易缓冲区的最大长度（字节），需保证据此创建的交易账户不超过单次分配上限
+const MAX_TRANSACTION_BUFFER_SIZE: usize = 8192;
+
+// 定义常量：单个提案可附加的最大余额断言数量
+const MAX_BALANCE_ASSERTIONS: usize = 4;
+
+// 定义常量：guard 程序执行前、执行后钩子的指令名
+const GUARD_PRE_EXECUTE: &str = "pre_execute";
+const GUARD_POST_EXECUTE: &str = "post_execute";
+
+// 定义成员权限位：发起提案
+#[constant]
+pub const PERMISSION_PROPOSE: u8 = 1 << 0;
+
+// 定义成员权限位：投票（批准、撤销、拒绝）
+#[constant]
+pub const PERMISSION_VOTE: u8 = 1 << 1;
+
+// 定义成员权限位：执行提案
+#[constant]
+pub const PERMISSION_EXECUTE: u8 = 1 << 2;
+
+// 定义成员全部权限
+#[constant]
+pub const PERMISSION_ALL: u8 = PERMISSION_PROPOSE | PERMISSION_VOTE | PERMISSION_EXECUTE;
+
+// 定义常量：指定执行者列表的最大数量
+const MAX_EXECUTORS: usize = 10;
+
+// 定义常量：时间锁的最大延迟（秒），防止设置过长导致资金无法动用
+const MAX_TIME_LOCK_SECONDS: u32 = 90 * 24 * 60 * 60;
+
 // 声明程序的唯一 ID，标识此 Solana 程序
 declare_id!("FZoTboRWj9fe74mx2E8sKDM8pVSov2n3QNdmRxTLLFEY");
 
@@ -39,22 +125,27 @@ pub mod multisig {
     // 创建多签账户的指令
     pub fn create_multisig(
         ctx: Context<CreateMultisig>, // 上下文，包含账户信息
-        owners: Vec<Pubkey>,          // 多签账户的 owner 公钥列表
-        threshold: u8,                // 批准交易所需的签名数量
+        owners: Vec<Member>,          // 多签账户的成员列表（公钥及权限）
+        threshold: u16,               // 批准交易所需的投票权重之和
         nonce: u64,                   // 用于生成 PDA 的随机数
     ) -> Result<()> {
         // 验证 owner 列表不为空
         require!(!owners.is_empty(), MultisigError::InvalidOwners);
         // 验证 owner 数量不超过最大限制
         require!(owners.len() <= MAX_OWNERS, MultisigError::TooManyOwners);
-        // 验证 threshold 大于 0 且不超过 owner 数量
+        // 验证每个成员的权限和权重有效
+        for member in &owners {
+            validate_permissions(member.permissions)?;
+            validate_weight(member.weight)?;
+        }
+        // 验证 threshold 大于 0 且不超过拥有投票权的成员权重之和
         require!(
-            threshold > 0 && (threshold as usize) <= owners.len(),
+            threshold > 0 && threshold as u32 <= total_vote_weight(&owners),
             MultisigError::InvalidThreshold
         );
 
         // 使用 HashSet 检查 owner 列表中是否有重复公钥
-        let unique_owners: HashSet<Pubkey> = owners.iter().cloned().collect();
+        let unique_owners: HashSet<Pubkey> = owners.iter().map(|m| m.key).collect();
         require!(
             unique_owners.len() == owners.len(),
             MultisigError::DuplicateOwners
@@ -65,15 +156,30 @@ pub mod multisig {
         ms.bump = ctx.bumps.multisig; // 设置多签 PDA 的 bump seed
         ms.vault_bump = ctx.bumps.vault; // 设置金库 PDA 的 bump seed
         ms.whitelist_bump = ctx.bumps.whitelist; // 设置白名单 PDA 的 bump seed
+        ms.address_book_bump = ctx.bumps.address_book; // 设置地址簿 PDA 的 bump seed
         ms.owners = owners; // 设置 owner 列表
         ms.threshold = threshold; // 设置批准阈值
+        ms.config_threshold = threshold; // 配置变更阈值默认与批准阈值相同
         ms.next_tx_id = 0; // 初始化交易 ID
         ms.paused = false; // 设置账户未暂停
         ms.nonce = nonce; // 设置 nonce 值
+        ms.config_generation = 0; // 初始化配置代数
+        ms.time_lock_seconds = 0; // 默认不启用时间锁
+        ms.executor_policy = ExecutorPolicy::OwnersOnly; // 默认仅拥有执行权限的成员可执行
+        ms.executors = vec![]; // 初始化指定执行者列表
+        ms.guard_program = None; // 默认不启用 guard 程序
 
         // 初始化白名单账户，包含系统程序和当前程序
         let whitelist = &mut ctx.accounts.whitelist;
-        whitelist.programs = vec![system_program::ID, crate::ID];
+        whitelist.programs = vec![
+            WhitelistEntry::new(system_program::ID),
+            WhitelistEntry::new(crate::ID),
+        ];
+
+        // 初始化地址簿账户，默认不启用
+        let address_book = &mut ctx.accounts.address_book;
+        address_book.enabled = false;
+        address_book.addresses = vec![];
 
         // 触发多签创建事件
         emit!(MultisigCreated {
@@ -87,88 +193,254 @@ pub mod multisig {
         Ok(())
     }
 
-    // 提出新交易的指令
+    // 提出新交易的指令，参数直接对应 IDL 中的参数列表
+    #[allow(clippy::too_many_arguments)]
     pub fn propose(
         ctx: Context<Propose>,              // 上下文，包含账户信息
         instructions: Vec<InstructionData>, // 交易包含的指令列表
         expires_at: Option<i64>,            // 交易过期时间（可选）
         auto_approve: bool,                 // 是否自动批准
+        vault_index: u8,                    // 签名金库的索引，0 为旧版金库
+        ephemeral_signers: u8,              // 声明的临时签名者数量
+        execution_mode: ExecutionMode,      // 执行方式：原子或分步
+        assertions: Vec<BalanceAssertion>,  // 执行后需成立的余额断言
     ) -> Result<()> {
-        // 获取多签账户
-        let ms = &mut ctx.accounts.multisig;
-        // 确保多签账户未暂停
-        // require!(!ms.paused, MultisigError::Paused);
-        let is_resume_proposal =
-            instructions.len() == 1 && is_pause_instruction(&instructions[0], &crate::ID, false);
-        if !is_resume_proposal && ms.paused {
-            return err!(MultisigError::Paused);
-        }
-        // 验证指令列表不为空且不超过最大限制
+        // 将指令列表编译为紧凑消息
+        let message = TransactionMessage::compile(&instructions)?;
+        let accounts = ctx.accounts;
+        create_proposal(
+            &mut accounts.multisig,
+            &accounts.whitelist,
+            &accounts.address_book,
+            accounts.vault.key(),
+            &mut accounts.transaction,
+            accounts.proposer.key(),
+            ProposalArgs {
+                message,
+                loaded_addresses: LoadedAddresses::default(),
+                expires_at,
+                auto_approve,
+                vault_index,
+                ephemeral_signers,
+                from_buffer: false,
+                execution_mode,
+                assertions,
+            },
+        )
+    }
+
+    // 提出原生 SOL 转账交易的指令，在链上构造转账指令
+    pub fn propose_sol_transfer(
+        ctx: Context<ProposeTransfer>, // 上下文，包含账户信息
+        to: Pubkey,                    // 收款地址
+        lamports: u64,                 // 转账金额（lamports）
+        expires_at: Option<i64>,       // 交易过期时间（可选）
+        auto_approve: bool,            // 是否自动批准
+        vault_index: u8,               // 签名金库的索引，0 为旧版金库
+    ) -> Result<()> {
+        // 验证金额和收款地址
+        require!(lamports > 0, MultisigError::InvalidAmount);
+        let vault = ctx.accounts.vault.key();
+        validate_transfer_destination(&to, &vault)?;
+        // 构造由金库签名的系统转账指令
+        let ix = system_instruction::transfer(&vault, &to, lamports);
+        let accounts = ctx.accounts;
+        create_proposal(
+            &mut accounts.multisig,
+            &accounts.whitelist,
+            &accounts.address_book,
+            vault,
+            &mut accounts.transaction,
+            accounts.proposer.key(),
+            ProposalArgs {
+                message: TransactionMessage::compile(&[ix.into()])?,
+                loaded_addresses: LoadedAddresses::default(),
+                expires_at,
+                auto_approve,
+                vault_index,
+                ephemeral_signers: 0,
+                from_buffer: false,
+                execution_mode: ExecutionMode::Atomic,
+                assertions: vec![],
+            },
+        )
+    }
+
+    // 提出 SPL 代币转账交易的指令，在链上构造金库关联代币账户之间的 transfer_checked 指令；
+    // mint 账户需作为第一个剩余账户传入，用于读取精度和所属代币程序
+    pub fn propose_token_transfer(
+        ctx: Context<ProposeTransfer>, // 上下文，包含账户信息
+        mint: Pubkey,                  // 代币 mint
+        to: Pubkey,                    // 收款钱包地址
+        amount: u64,                   // 转账数量（最小单位）
+        expires_at: Option<i64>,       // 交易过期时间（可选）
+        auto_approve: bool,            // 是否自动批准
+        vault_index: u8,               // 签名金库的索引，0 为旧版金库
+    ) -> Result<()> {
+        // 验证金额和收款地址
+        require!(amount > 0, MultisigError::InvalidAmount);
+        let vault = ctx.accounts.vault.key();
+        validate_transfer_destination(&to, &vault)?;
+        // 读取 mint 账户，确认其属于 Token 或 Token-2022 程序
+        let mint_info = ctx
+            .remaining_accounts
+            .first()
+            .filter(|info| info.key() == mint)
+            .ok_or(MultisigError::InvalidMint)?;
+        let token_program = *mint_info.owner;
         require!(
-            !instructions.is_empty() && instructions.len() <= MAX_IX_PER_TX,
-            MultisigError::TooManyInstructions
+            token_program == token::ID || token_program == token_2022::ID,
+            MultisigError::InvalidMint
         );
+        let decimals = Mint::try_deserialize(&mut &mint_info.try_borrow_data()?[..])?.decimals;
+        // 构造金库关联代币账户到收款人关联代币账户的转账指令
+        let ix = token_2022::spl_token_2022::instruction::transfer_checked(
+            &token_program,
+            &get_associated_token_address_with_program_id(&vault, &mint, &token_program),
+            &mint,
+            &get_associated_token_address_with_program_id(&to, &mint, &token_program),
+            &vault,
+            &[],
+            amount,
+            decimals,
+        )?;
+        let accounts = ctx.accounts;
+        create_proposal(
+            &mut accounts.multisig,
+            &accounts.whitelist,
+            &accounts.address_book,
+            vault,
+            &mut accounts.transaction,
+            accounts.proposer.key(),
+            ProposalArgs {
+                message: TransactionMessage::compile(&[ix.into()])?,
+                loaded_addresses: LoadedAddresses::default(),
+                expires_at,
+                auto_approve,
+                vault_index,
+                ephemeral_signers: 0,
+                from_buffer: false,
+                execution_mode: ExecutionMode::Atomic,
+                assertions: vec![],
+            },
+        )
+    }
 
-        // 获取提议者公钥
-        let proposer_key = ctx.accounts.proposer.key();
-        // 确保提议者是 owner 之一
-        require!(ms.owners.contains(&proposer_key), MultisigError::NotAnOwner);
+    // 创建交易缓冲区的指令，用于分多笔 Solana 交易上传超出单笔提案限制的指令列表
+    pub fn create_transaction_buffer(
+        ctx: Context<CreateTransactionBuffer>, // 上下文，包含账户信息
+        buffer_index: u8,                      // 缓冲区索引，区分同一成员的多个缓冲区
+        vault_index: u8,                       // 签名金库的索引
+        final_hash: [u8; 32],                  // 完整内容的 SHA-256 哈希
+        final_size: u32,                       // 完整内容的长度（字节）
+        buffer: Vec<u8>,                       // 首个数据块
+    ) -> Result<()> {
+        // 确保创建者是拥有提案权限的成员
+        let ms = &ctx.accounts.multisig;
+        let creator_key = ctx.accounts.creator.key();
+        require!(ms.is_member(&creator_key), MultisigError::NotAnOwner);
+        require!(
+            ms.has_permission(&creator_key, PERMISSION_PROPOSE),
+            MultisigError::MissingPermission
+        );
+        // 验证首个数据块不超过声明的完整长度
+        require!(
+            buffer.len() <= final_size as usize,
+            MultisigError::TransactionBufferOverflow
+        );
 
-        // 验证过期时间（若设置）晚于当前时间
-        if let Some(exp) = expires_at {
-            let now = Clock::get()?.unix_timestamp;
-            require!(exp > now, MultisigError::InvalidExpiration);
-        }
+        // 初始化交易缓冲区
+        let transaction_buffer = &mut ctx.accounts.transaction_buffer;
+        transaction_buffer.multisig = ms.key(); // 设置关联的多签账户
+        transaction_buffer.creator = creator_key; // 设置创建者
+        transaction_buffer.buffer_index = buffer_index; // 设置缓冲区索引
+        transaction_buffer.vault_index = vault_index; // 设置签名金库的索引
+        transaction_buffer.final_hash = final_hash; // 设置完整内容的哈希
+        transaction_buffer.final_size = final_size; // 设置完整内容的长度
+        transaction_buffer.buffer = buffer; // 写入首个数据块
 
-        // 获取白名单并验证指令的程序 ID 是否在白名单中
-        let whitelist = &ctx.accounts.whitelist;
-        for ix in &instructions {
-            require!(
-                whitelist.programs.contains(&ix.program_id),
-                MultisigError::ProgramNotAllowed
-            );
-            // 验证指令中的 signer 是金库账户
-            require!(
-                is_signer_allowed(ix, &ctx.accounts.vault.key()),
-                MultisigError::SignerNotAllowed
-            );
-            // 验证指令的账户和数据长度
-            validate_ix_bounds(ix)?;
-        }
+        // 触发交易缓冲区创建事件
+        emit!(TransactionBufferCreated {
+            multisig: ms.key(),
+            transaction_buffer: transaction_buffer.key(),
+            creator: creator_key,
+            final_size,
+        });
 
-        // 初始化交易账户
-        let tx = &mut ctx.accounts.transaction;
-        tx.multisig = ms.key(); // 设置关联的多签账户
-        tx.id = ms.next_tx_id; // 设置交易 ID
-        tx.proposer = proposer_key; // 设置提议者
-        tx.instructions = instructions; // 设置指令列表
-        tx.approvals = vec![]; // 初始化批准列表
-        tx.executed = false; // 设置交易未执行
-        tx.expires_at = expires_at; // 设置过期时间
-
-        // 如果启用自动批准，调用批准逻辑
-        if auto_approve {
-            approve_impl(ms, tx, &proposer_key)?;
-        }
-
-        // 更新下一个交易 ID，防止溢出
-        ms.next_tx_id = ms
-            .next_tx_id
-            .checked_add(1)
+        // 返回成功
+        Ok(())
+    }
+
+    // 向交易缓冲区追加数据块的指令
+    pub fn extend_transaction_buffer(
+        ctx: Context<ExtendTransactionBuffer>, // 上下文，包含账户信息
+        buffer: Vec<u8>,                       // 追加的数据块
+    ) -> Result<()> {
+        let transaction_buffer = &mut ctx.accounts.transaction_buffer;
+        // 验证追加后不超过声明的完整长度
+        let new_len = transaction_buffer
+            .buffer
+            .len()
+            .checked_add(buffer.len())
             .ok_or(MultisigError::Overflow)?;
+        require!(
+            new_len <= transaction_buffer.final_size as usize,
+            MultisigError::TransactionBufferOverflow
+        );
+        // 追加数据块
+        transaction_buffer.buffer.extend_from_slice(&buffer);
 
-        // 触发提案创建事件
-        emit!(ProposalCreated {
-            multisig: ms.key(),
-            transaction: tx.key(),
-            proposer: proposer_key,
-            instruction_count: tx.instructions.len() as u64,
-        });
+        // 返回成功
+        Ok(())
+    }
 
+    // 关闭交易缓冲区的指令，租金退还给创建者
+    pub fn close_transaction_buffer(_ctx: Context<CloseTransactionBuffer>) -> Result<()> {
         // 返回成功
         Ok(())
     }
 
+    // 将已上传完整的交易缓冲区转换为提案的指令，缓冲区随之关闭
+    pub fn propose_from_buffer(
+        ctx: Context<ProposeFromBuffer>,   // 上下文，包含账户信息
+        expires_at: Option<i64>,           // 交易过期时间（可选）
+        auto_approve: bool,                // 是否自动批准
+        ephemeral_signers: u8,             // 声明的临时签名者数量
+        execution_mode: ExecutionMode,     // 执行方式：原子或分步
+        assertions: Vec<BalanceAssertion>, // 执行后需成立的余额断言
+    ) -> Result<()> {
+        // 验证缓冲区内容完整且与声明的哈希一致
+        let transaction_buffer = &ctx.accounts.transaction_buffer;
+        transaction_buffer.validate()?;
+        // 反序列化紧凑消息
+        let message = TransactionMessage::try_from_slice(&transaction_buffer.buffer)
+            .map_err(|_| MultisigError::InvalidTransactionBuffer)?;
+        // 消息引用的地址查找表按顺序作为剩余账户传入，用于按实际账户校验提案
+        let loaded_addresses = message.load_addresses(ctx.remaining_accounts)?;
+        let vault_index = transaction_buffer.vault_index;
+
+        let accounts = ctx.accounts;
+        create_proposal(
+            &mut accounts.multisig,
+            &accounts.whitelist,
+            &accounts.address_book,
+            accounts.vault.key(),
+            &mut accounts.transaction,
+            accounts.proposer.key(),
+            ProposalArgs {
+                message,
+                loaded_addresses,
+                expires_at,
+                auto_approve,
+                vault_index,
+                ephemeral_signers,
+                from_buffer: true,
+                execution_mode,
+                assertions,
+            },
+        )
+    }
+
     // 批准交易的指令
     pub fn approve(ctx: Context<Approve>) -> Result<()> {
         // 获取多签和交易账户
@@ -177,13 +449,16 @@ pub mod multisig {
         let owner_key = ctx.accounts.owner.key(); // 获取批准者公钥
                                                   // 确保多签账户未暂停
                                                   // require!(!ms.paused, MultisigError::Paused);
-        let is_resume_proposal = tx.instructions.len() == 1
-            && is_pause_instruction(&tx.instructions[0], &crate::ID, false);
-        if !is_resume_proposal && ms.paused {
+        if !tx.is_resume_proposal()? && ms.paused {
             return err!(MultisigError::Paused);
         }
-        // 确保交易尚未执行
-        require!(!tx.executed, MultisigError::AlreadyExecuted);
+        // 确保提案处于可投票状态
+        tx.require_pending()?;
+        // 确保提案之后 owner 集合或阈值未发生变更
+        require!(
+            tx.config_generation == ms.config_generation,
+            MultisigError::StaleProposal
+        );
 
         // 验证交易未过期
         if let Some(exp) = tx.expires_at {
@@ -191,13 +466,14 @@ pub mod multisig {
         }
 
         // 执行批准逻辑
-        approve_impl(ms, tx, &owner_key)?;
+        approve_impl(ms, &ctx.accounts.whitelist, tx, &owner_key)?;
 
-        // 触发批准事件
+        // 触发批准事件，附带当前 owner 的有效批准数
         emit!(ApprovalAdded {
             multisig: ms.key(),
             transaction: tx.key(),
             owner: owner_key,
+            approval_weight: ms.vote_weight(&tx.approvals),
         });
 
         // 返回成功
@@ -212,16 +488,19 @@ pub mod multisig {
         let owner_key = ctx.accounts.owner.key(); // 获取撤销者公钥
                                                   // 确保多签账户未暂停
         require!(!ms.paused, MultisigError::Paused);
-        // 确保交易尚未执行
-        require!(!tx.executed, MultisigError::AlreadyExecuted);
+        // 确保提案处于可投票状态
+        tx.require_pending()?;
 
         // 验证交易未过期
         if let Some(exp) = tx.expires_at {
             require!(Clock::get()?.unix_timestamp <= exp, MultisigError::Expired);
         }
 
-        // 确保撤销者是 owner
-        require!(ms.owners.contains(&owner_key), MultisigError::NotAnOwner);
+        // 确保撤销者是拥有投票权限的成员
+        require!(
+            ms.has_permission(&owner_key, PERMISSION_VOTE),
+            MultisigError::MissingPermission
+        );
 
         // 记录批准列表长度
         let old_len = tx.approvals.len();
@@ -229,14 +508,86 @@ pub mod multisig {
         tx.approvals.retain(|k| k != &owner_key);
         // 确保批准被移除
         require!(tx.approvals.len() < old_len, MultisigError::NotApproved);
+        // 批准数回落到阈值以下时，提案回到投票阶段
+        sync_approval_status(
+            ms,
+            &ctx.accounts.whitelist,
+            tx,
+            Clock::get()?.unix_timestamp,
+        )?;
 
-        // 触发撤销事件
+        // 触发撤销事件，附带当前 owner 的有效批准数
         emit!(ApprovalRevoked {
             multisig: ms.key(),
             transaction: tx.key(),
             owner: owner_key,
+            approval_weight: ms.vote_weight(&tx.approvals),
+        });
+
+        // 返回成功
+        Ok(())
+    }
+
+    // 拒绝交易的指令
+    pub fn reject(ctx: Context<Approve>) -> Result<()> {
+        // 获取多签和交易账户
+        let ms = &ctx.accounts.multisig;
+        let tx = &mut ctx.accounts.transaction;
+        let owner_key = ctx.accounts.owner.key(); // 获取拒绝者公钥
+        if !tx.is_resume_proposal()? && ms.paused {
+            return err!(MultisigError::Paused);
+        }
+        // 确保提案处于可投票状态
+        tx.require_pending()?;
+        // 确保提案之后 owner 集合或阈值未发生变更
+        require!(
+            tx.config_generation == ms.config_generation,
+            MultisigError::StaleProposal
+        );
+
+        // 验证交易未过期
+        if let Some(exp) = tx.expires_at {
+            require!(Clock::get()?.unix_timestamp <= exp, MultisigError::Expired);
+        }
+
+        // 确保拒绝者拥有投票权限
+        require!(
+            ms.has_permission(&owner_key, PERMISSION_VOTE),
+            MultisigError::MissingPermission
+        );
+        // 确保未重复拒绝
+        require!(
+            !tx.rejections.contains(&owner_key),
+            MultisigError::AlreadyRejected
+        );
+        // 拒绝视为改票，移除该 owner 之前的批准
+        let now = Clock::get()?.unix_timestamp;
+        tx.approvals.retain(|k| k != &owner_key);
+        let whitelist = &ctx.accounts.whitelist;
+        sync_approval_status(ms, whitelist, tx, now)?;
+        // 计算该提案适用的阈值
+        let threshold = ms.threshold_for(whitelist, &tx.message.program_ids()?);
+        // 添加拒绝者到拒绝列表
+        tx.rejections.push(owner_key);
+
+        // 触发拒绝事件
+        emit!(RejectionAdded {
+            multisig: ms.key(),
+            transaction: tx.key(),
+            owner: owner_key,
+            rejection_weight: ms.vote_weight(&tx.rejections),
         });
 
+        // 剩余 owner 已不可能达到阈值时，提案永久失效
+        if !ms.can_reach_threshold(&tx.rejections, threshold) {
+            tx.transition_to(ProposalStatus::Rejected, now)?;
+            // 触发提案被拒绝事件
+            emit!(ProposalRejected {
+                multisig: ms.key(),
+                transaction: tx.key(),
+            });
+        }
+
         // 返回成功
         Ok(())
     }
@@ -244,12 +595,17 @@ pub mod multisig {
     // 取消提案的指令
     pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
         // 获取交易账户
-        let tx = &ctx.accounts.transaction;
-        // 确保交易尚未执行
-        require!(!tx.executed, MultisigError::AlreadyExecuted);
+        let tx = &mut ctx.accounts.transaction;
+        // 确保提案处于可取消状态，分步执行中的提案也可取消以中止剩余步骤
+        if tx.status != ProposalStatus::Executing {
+            tx.require_pending()?;
+        }
         // 确保提案无批准记录
         // require!(tx.approvals.is_empty(), MultisigError::CannotCancelApprovedProposal);
 
+        // 标记提案为已取消，账户保留供查询，之后可通过 close_transaction 回收租金
+        tx.transition_to(ProposalStatus::Cancelled, Clock::get()?.unix_timestamp)?;
+
         // 触发提案取消事件
         emit!(ProposalCancelled {
             multisig: ctx.accounts.multisig.key(),
@@ -261,85 +617,193 @@ pub mod multisig {
         Ok(())
     }
 
-    // 执行交易的指令
-    pub fn execute(ctx: Context<Execute>) -> Result<()> {
+    // 刷新过时提案的指令：按当前配置重新验证指令，清空旧配置下的批准并记录当前配置代数
+    pub fn refresh_proposal(ctx: Context<RefreshProposal>) -> Result<()> {
         // 获取多签和交易账户
         let ms = &ctx.accounts.multisig;
         let tx = &mut ctx.accounts.transaction;
         // 确保多签账户未暂停
-        // require!(!ms.paused, MultisigError::Paused);
-        let is_resume_proposal = tx.instructions.len() == 1
-            && is_pause_instruction(&tx.instructions[0], &crate::ID, false);
-        if !is_resume_proposal && ms.paused {
-            return err!(MultisigError::Paused);
-        }
-        // 确保交易尚未执行
-        require!(!tx.executed, MultisigError::AlreadyExecuted);
+        require!(!ms.paused, MultisigError::Paused);
+        // 确保提案处于可投票状态
+        tx.require_pending()?;
+        // 确保提案确实已过时
+        require!(
+            tx.config_generation != ms.config_generation,
+            MultisigError::ProposalNotStale
+        );
 
         // 验证交易未过期
         if let Some(exp) = tx.expires_at {
             require!(Clock::get()?.unix_timestamp <= exp, MultisigError::Expired);
         }
-        // 确保批准数量达到阈值
+
+        // 按当前白名单和地址簿重新验证指令，配置收紧后不再合规的提案不能通过刷新恢复；
+        // 使用地址查找表的提案需按顺序传入查找表作为剩余账户
+        let loaded = tx.message.load_addresses(ctx.remaining_accounts)?;
+        let (vault_key, _) = ms.vault_address(&ms.key(), tx.vault_index)?;
+        let tx_key = tx.key();
+        let ephemeral_keys: Vec<Pubkey> = (0..tx.ephemeral_signer_bumps.len() as u8)
+            .map(|index| ephemeral_signer_address(&tx_key, index).0)
+            .collect();
+        validate_instructions(
+            &ms.key(),
+            &ctx.accounts.whitelist,
+            &ctx.accounts.address_book,
+            vault_key,
+            &ephemeral_keys,
+            &tx.message.instructions(&loaded)?,
+        )?;
+
+        // 丢弃旧配置下收集的批准，并记录当前配置代数
+        tx.approvals.clear();
+        tx.rejections.clear();
+        tx.config_generation = ms.config_generation;
+        sync_approval_status(
+            ms,
+            &ctx.accounts.whitelist,
+            tx,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        // 触发提案刷新事件
+        emit!(ProposalRefreshed {
+            multisig: ms.key(),
+            transaction: tx.key(),
+            config_generation: tx.config_generation,
+        });
+
+        // 返回成功
+        Ok(())
+    }
+
+    // 执行交易的指令，原子模式的提案在一笔交易内执行全部指令
+    pub fn execute<'info>(ctx: Context<'_, '_, '_, 'info, Execute<'info>>) -> Result<()> {
+        // 获取多签和交易账户
+        let ms = &ctx.accounts.multisig;
+        let tx = &mut ctx.accounts.transaction;
+        // 分步执行的提案需逐条调用 execute_step
         require!(
-            tx.approvals.len() as u8 >= ms.threshold,
-            MultisigError::NotEnoughApprovals
+            tx.execution_mode == ExecutionMode::Atomic,
+            MultisigError::StepwiseExecutionRequired
         );
+        // 确保提案满足执行条件
+        let now = Clock::get()?.unix_timestamp;
+        require_executable(ms, &ctx.accounts.whitelist, tx, now)?;
 
-        // 获取多签账户公钥
-        let multisig_key = ms.key();
-        // 设置金库的 PDA seed
-        let bump_seed = [ms.vault_bump];
-        let seeds = &[b"vault".as_ref(), multisig_key.as_ref(), bump_seed.as_ref()];
-        let vault_seeds: &[&[&[u8]]] = &[seeds];
+        // 执行前记录余额断言的基准余额
+        let baselines = balance_baselines(&tx.assertions, ctx.remaining_accounts)?;
 
         // 执行交易中的所有指令
-        for ix in &tx.instructions {
-            let instruction = anchor_lang::solana_program::instruction::Instruction {
-                program_id: ix.program_id,
-                accounts: to_account_metas(&ix.accounts), // 转换账户元数据
-                data: ix.data.clone(),
-            };
-            // 使用金库签名调用指令
-            invoke_signed(&instruction, &ctx.remaining_accounts, vault_seeds)?;
-        }
+        let num_instructions = tx.message.instructions.len();
+        execute_instructions(ms, tx, ctx.remaining_accounts, 0..num_instructions)?;
+
+        // 全部指令执行后检查余额断言，不成立时整笔执行回滚
+        check_balance_assertions(&tx.assertions, ctx.remaining_accounts, &baselines)?;
 
         // 标记交易为已执行
-        tx.executed = true;
+        tx.transition_to(ProposalStatus::Executed, now)?;
 
-        // 触发交易执行事件
+        // 触发交易执行事件，记录执行者
         emit!(TransactionExecuted {
             multisig: ms.key(),
             transaction: tx.key(),
+            executor: ctx.accounts.executor.key(),
+        });
+
+        // 返回成功
+        Ok(())
+    }
+
+    // 分步执行交易的指令，每次执行下一条指令；
+    // 某一步失败时整笔交易回滚，提案停留在上一步完成后的状态，可重新执行该步
+    pub fn execute_step<'info>(ctx: Context<'_, '_, '_, 'info, Execute<'info>>) -> Result<()> {
+        // 获取多签和交易账户
+        let ms = &ctx.accounts.multisig;
+        let tx = &mut ctx.accounts.transaction;
+        // 原子模式的提案只能通过 execute 一次执行
+        require!(
+            tx.execution_mode == ExecutionMode::Stepwise,
+            MultisigError::AtomicExecutionRequired
+        );
+        let now = Clock::get()?.unix_timestamp;
+        if tx.status == ProposalStatus::Executing {
+            // 已开始执行的提案无需重新检查批准和过期，但多签暂停或配置变更后不能继续
+            require_continuable(ms, tx)?;
+        } else {
+            // 第一步执行前检查全部执行条件，并进入执行中状态
+            require_executable(ms, &ctx.accounts.whitelist, tx, now)?;
+            tx.transition_to(ProposalStatus::Executing, now)?;
+        }
+
+        // 执行下一条指令
+        let ix_index = tx.next_ix_index;
+        let step = ix_index as usize;
+        execute_instructions(ms, tx, ctx.remaining_accounts, step..step + 1)?;
+        tx.next_ix_index = ix_index.checked_add(1).ok_or(MultisigError::Overflow)?;
+        // 本提案自身的步骤可能变更配置，同步配置代数，使后续步骤不被视为过期
+        ctx.accounts.multisig.reload()?;
+        let ms = &ctx.accounts.multisig;
+        tx.config_generation = ms.config_generation;
+        let remaining = tx.message.instructions.len() - tx.next_ix_index as usize;
+
+        // 触发分步执行事件
+        emit!(TransactionStepExecuted {
+            multisig: ms.key(),
+            transaction: tx.key(),
+            executor: ctx.accounts.executor.key(),
+            ix_index,
+            remaining: remaining as u16,
         });
 
+        // 最后一条指令执行后标记交易为已执行
+        if remaining == 0 {
+            tx.transition_to(ProposalStatus::Executed, now)?;
+            emit!(TransactionExecuted {
+                multisig: ms.key(),
+                transaction: tx.key(),
+                executor: ctx.accounts.executor.key(),
+            });
+        }
+
         // 返回成功
         Ok(())
     }
 
     // 关闭交易账户的指令
-    pub fn close_transaction(_ctx: Context<CloseTransaction>) -> Result<()> {
+    pub fn close_transaction(ctx: Context<CloseTransaction>) -> Result<()> {
+        // 获取交易账户
+        let tx = &mut ctx.accounts.transaction;
+        // 已过期但仍在投票阶段的提案先迁移为过期状态
+        let now = Clock::get()?.unix_timestamp;
+        if tx.is_pending() && tx.expires_at.is_some_and(|exp| now > exp) {
+            tx.transition_to(ProposalStatus::Expired, now)?;
+        }
+        // 只有处于终结状态的提案才能关闭
+        require!(tx.status.is_final(), MultisigError::TransactionNotClosable);
         // 触发交易关闭事件
         emit!(TransactionClosed {
-            multisig: _ctx.accounts.multisig.key(),
-            transaction: _ctx.accounts.transaction.key(),
-            recipient: _ctx.accounts.recipient.key(),
+            multisig: ctx.accounts.multisig.key(),
+            transaction: tx.key(),
+            recipient: ctx.accounts.proposer.key(),
+            closer: ctx.accounts.closer.key(),
         });
         // 返回成功
         Ok(())
     }
 
     // 修改多签阈值的指令
-    pub fn change_threshold(ctx: Context<Manage>, new_threshold: u8) -> Result<()> {
+    pub fn change_threshold(ctx: Context<Manage>, new_threshold: u16) -> Result<()> {
         // 获取多签账户
         let ms = &mut ctx.accounts.multisig;
-        // 验证新阈值有效
+        // 验证新阈值有效，不超过拥有投票权的成员权重之和
         require!(
-            new_threshold > 0 && (new_threshold as usize) <= ms.owners.len(),
+            new_threshold > 0 && new_threshold as u32 <= ms.total_vote_weight(),
             MultisigError::InvalidThreshold
         );
         // 更新阈值
         ms.threshold = new_threshold;
+        // 递增配置代数，使未执行的提案失效
+        ms.bump_config_generation()?;
         // 触发阈值变更事件
         emit!(ThresholdChanged {
             multisig: ms.key(),
@@ -349,20 +813,58 @@ pub mod multisig {
         Ok(())
     }
 
+    // 修改配置变更阈值的指令
+    pub fn change_config_threshold(ctx: Context<Manage>, new_config_threshold: u16) -> Result<()> {
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 验证新阈值有效，不超过拥有投票权的成员权重之和
+        require!(
+            new_config_threshold > 0 && new_config_threshold as u32 <= ms.total_vote_weight(),
+            MultisigError::InvalidThreshold
+        );
+        // 更新配置变更阈值
+        ms.config_threshold = new_config_threshold;
+        // 递增配置代数，使未执行的提案失效
+        ms.bump_config_generation()?;
+        // 触发配置变更阈值变更事件
+        emit!(ConfigThresholdChanged {
+            multisig: ms.key(),
+            new_config_threshold
+        });
+        // 返回成功
+        Ok(())
+    }
+
     // 添加新 owner 的指令
-    pub fn add_owner(ctx: Context<Manage>, new_owner: Pubkey) -> Result<()> {
+    pub fn add_owner(
+        ctx: Context<Manage>,
+        new_owner: Pubkey,
+        permissions: u8,
+        weight: u16,
+    ) -> Result<()> {
+        // 验证权限和权重有效
+        validate_permissions(permissions)?;
+        validate_weight(weight)?;
         // 获取多签账户
         let ms = &mut ctx.accounts.multisig;
         // 确保新 owner 未存在
-        require!(!ms.owners.contains(&new_owner), MultisigError::OwnerExists);
+        require!(!ms.is_member(&new_owner), MultisigError::OwnerExists);
         // 确保 owner 数量未超限
         require!(ms.owners.len() < MAX_OWNERS, MultisigError::TooManyOwners);
         // 添加新 owner
-        ms.owners.push(new_owner);
+        ms.owners.push(Member {
+            key: new_owner,
+            permissions,
+            weight,
+        });
+        // 递增配置代数，使未执行的提案失效
+        ms.bump_config_generation()?;
         // 触发 owner 添加事件
         emit!(OwnerAdded {
             multisig: ms.key(),
-            new_owner
+            new_owner,
+            permissions,
+            weight
         });
         // 返回成功
         Ok(())
@@ -375,14 +877,17 @@ pub mod multisig {
         // 记录当前 owner 数量
         let old_len = ms.owners.len();
         // 移除指定 owner
-        ms.owners.retain(|k| k != &owner);
+        ms.owners.retain(|m| m.key != owner);
         // 确保 owner 被移除
         require!(ms.owners.len() < old_len, MultisigError::NotAnOwner);
-        // 确保阈值仍然有效
+        // 确保阈值仍然有效，拥有投票权的成员权重足以达到阈值
         require!(
-            (ms.threshold as usize) <= ms.owners.len(),
+            ms.threshold as u32 <= ms.total_vote_weight()
+                && ms.config_threshold as u32 <= ms.total_vote_weight(),
             MultisigError::InvalidThresholdAfterRemoval
         );
+        // 递增配置代数，使未执行的提案失效
+        ms.bump_config_generation()?;
         // 触发 owner 移除事件
         emit!(OwnerRemoved {
             multisig: ms.key(),
@@ -392,6 +897,37 @@ pub mod multisig {
         Ok(())
     }
 
+    // 修改 owner 投票权重的指令
+    pub fn change_owner_weight(ctx: Context<Manage>, owner: Pubkey, weight: u16) -> Result<()> {
+        // 验证权重有效
+        validate_weight(weight)?;
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 更新指定 owner 的权重
+        let member = ms
+            .owners
+            .iter_mut()
+            .find(|m| m.key == owner)
+            .ok_or(MultisigError::NotAnOwner)?;
+        member.weight = weight;
+        // 确保阈值仍然有效，拥有投票权的成员权重足以达到阈值
+        require!(
+            ms.threshold as u32 <= ms.total_vote_weight()
+                && ms.config_threshold as u32 <= ms.total_vote_weight(),
+            MultisigError::InvalidThreshold
+        );
+        // 递增配置代数，使未执行的提案失效
+        ms.bump_config_generation()?;
+        // 触发 owner 权重变更事件
+        emit!(OwnerWeightChanged {
+            multisig: ms.key(),
+            owner,
+            weight
+        });
+        // 返回成功
+        Ok(())
+    }
+
     // 暂停或恢复多签账户的指令
     pub fn pause(ctx: Context<Manage>, paused: bool) -> Result<()> {
         // 获取多签账户
@@ -407,13 +943,99 @@ pub mod multisig {
         Ok(())
     }
 
+    // 修改时间锁延迟的指令，0 表示关闭时间锁
+    pub fn change_time_lock(ctx: Context<Manage>, time_lock_seconds: u32) -> Result<()> {
+        // 验证延迟不超过最大限制
+        require!(
+            time_lock_seconds <= MAX_TIME_LOCK_SECONDS,
+            MultisigError::InvalidTimeLock
+        );
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 更新时间锁延迟
+        ms.time_lock_seconds = time_lock_seconds;
+        // 触发时间锁变更事件
+        emit!(TimeLockChanged {
+            multisig: ms.key(),
+            time_lock_seconds
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 修改执行者策略的指令，Designated 策略需提供执行者列表
+    pub fn set_executor_policy(
+        ctx: Context<Manage>,
+        policy: ExecutorPolicy,
+        executors: Vec<Pubkey>,
+    ) -> Result<()> {
+        // 验证执行者列表与策略匹配：仅 Designated 策略携带非空列表
+        require!(
+            (policy == ExecutorPolicy::Designated) != executors.is_empty(),
+            MultisigError::InvalidExecutors
+        );
+        // 验证执行者数量不超过最大限制
+        require!(
+            executors.len() <= MAX_EXECUTORS,
+            MultisigError::InvalidExecutors
+        );
+        // 使用 HashSet 检查执行者列表中是否有重复公钥
+        let unique_executors: HashSet<Pubkey> = executors.iter().cloned().collect();
+        require!(
+            unique_executors.len() == executors.len(),
+            MultisigError::InvalidExecutors
+        );
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 更新执行者策略
+        ms.executor_policy = policy;
+        ms.executors = executors;
+        // 触发执行者策略变更事件
+        emit!(ExecutorPolicyChanged {
+            multisig: ms.key(),
+            policy,
+            executors: ms.executors.clone(),
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 设置或清除 guard 程序的指令，执行提案前后会调用 guard 程序的钩子
+    pub fn set_guard_program(ctx: Context<Manage>, guard_program: Option<Pubkey>) -> Result<()> {
+        // guard 程序不能是本程序
+        require!(
+            guard_program != Some(crate::ID),
+            MultisigError::InvalidGuardProgram
+        );
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 更新 guard 程序
+        ms.guard_program = guard_program;
+        // 触发 guard 程序变更事件
+        emit!(GuardProgramChanged {
+            multisig: ms.key(),
+            guard_program,
+        });
+        // 返回成功
+        Ok(())
+    }
+
     // 添加程序到白名单的指令
-    pub fn add_to_whitelist(ctx: Context<ManageWhitelist>, program_id: Pubkey) -> Result<()> {
+    pub fn add_to_whitelist(
+        ctx: Context<ManageWhitelist>,
+        program_id: Pubkey,
+        required_approvals: u16,
+    ) -> Result<()> {
+        // 验证风险等级阈值可以达到
+        require!(
+            required_approvals as u32 <= ctx.accounts.multisig.total_vote_weight(),
+            MultisigError::InvalidThreshold
+        );
         // 获取白名单账户
         let whitelist = &mut ctx.accounts.whitelist;
         // 确保程序未在白名单中
         require!(
-            !whitelist.programs.contains(&program_id),
+            !whitelist.contains(&program_id),
             MultisigError::ProgramAlreadyWhitelisted
         );
         // 确保白名单未满
@@ -422,37 +1044,535 @@ pub mod multisig {
             MultisigError::WhitelistFull
         );
         // 添加程序到白名单
-        whitelist.programs.push(program_id);
+        whitelist.programs.push(WhitelistEntry {
+            program_id,
+            required_approvals,
+            allowed_prefixes: vec![],
+        });
         // 触发白名单添加事件
         emit!(WhitelistProgramAdded {
             multisig: ctx.accounts.multisig.key(),
-            program_id
+            program_id,
+            required_approvals
         });
         // 返回成功
         Ok(())
     }
 
-    // 从白名单移除程序的指令
-    pub fn remove_from_whitelist(ctx: Context<ManageWhitelist>, program_id: Pubkey) -> Result<()> {
-        // 防止移除核心程序（系统程序和当前程序）
-        require!(
-            program_id != system_program::ID && program_id != crate::ID,
-            MultisigError::CannotRemoveCoreProgram
-        );
-        // 获取白名单账户
-        let whitelist = &mut ctx.accounts.whitelist;
-        // 记录当前白名单长度
-        let old_len = whitelist.programs.len();
-        // 移除指定程序
-        whitelist.programs.retain(|p| p != &program_id);
-        // 确保程序被移除
+    // 修改白名单程序风险等级阈值的指令
+    pub fn update_whitelist_tier(
+        ctx: Context<ManageWhitelist>,
+        program_id: Pubkey,
+        required_approvals: u16,
+    ) -> Result<()> {
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 验证风险等级阈值可以达到
         require!(
-            whitelist.programs.len() < old_len,
-            MultisigError::ProgramNotFoundInWhitelist
+            required_approvals as u32 <= ms.total_vote_weight(),
+            MultisigError::InvalidThreshold
         );
-        // 触发白名单移除事件
-        emit!(WhitelistProgramRemoved {
+        // 更新指定程序的风险等级阈值
+        let entry = ctx
+            .accounts
+            .whitelist
+            .programs
+            .iter_mut()
+            .find(|e| e.program_id == program_id)
+            .ok_or(MultisigError::ProgramNotFoundInWhitelist)?;
+        entry.required_approvals = required_approvals;
+        // 递增配置代数，使未执行的提案失效
+        ms.bump_config_generation()?;
+        // 触发风险等级变更事件
+        emit!(WhitelistTierUpdated {
+            multisig: ms.key(),
+            program_id,
+            required_approvals
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 为白名单程序添加允许的指令前缀的指令
+    pub fn add_allowed_prefix(
+        ctx: Context<ManageWhitelist>,
+        program_id: Pubkey,
+        prefix: Vec<u8>,
+    ) -> Result<()> {
+        // 验证前缀非空且不超过最大长度
+        require!(
+            !prefix.is_empty() && prefix.len() <= MAX_PREFIX_LEN,
+            MultisigError::InvalidPrefix
+        );
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 查找白名单条目
+        let entry = ctx
+            .accounts
+            .whitelist
+            .programs
+            .iter_mut()
+            .find(|e| e.program_id == program_id)
+            .ok_or(MultisigError::ProgramNotFoundInWhitelist)?;
+        // 确保前缀未重复
+        require!(
+            !entry.allowed_prefixes.contains(&prefix),
+            MultisigError::PrefixAlreadyAllowed
+        );
+        // 确保前缀数量未超过最大限制
+        require!(
+            entry.allowed_prefixes.len() < MAX_PREFIXES_PER_PROGRAM,
+            MultisigError::TooManyPrefixes
+        );
+        // 添加前缀
+        entry.allowed_prefixes.push(prefix.clone());
+        // 递增配置代数，使按旧规则校验的提案失效
+        ms.bump_config_generation()?;
+        // 触发前缀添加事件
+        emit!(AllowedPrefixAdded {
+            multisig: ms.key(),
+            program_id,
+            prefix
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 从白名单程序移除允许的指令前缀的指令
+    pub fn remove_allowed_prefix(
+        ctx: Context<ManageWhitelist>,
+        program_id: Pubkey,
+        prefix: Vec<u8>,
+    ) -> Result<()> {
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 查找白名单条目
+        let entry = ctx
+            .accounts
+            .whitelist
+            .programs
+            .iter_mut()
+            .find(|e| e.program_id == program_id)
+            .ok_or(MultisigError::ProgramNotFoundInWhitelist)?;
+        // 确保前缀存在
+        require!(
+            entry.allowed_prefixes.contains(&prefix),
+            MultisigError::PrefixNotFound
+        );
+        // 前缀列表为空表示不限制指令，禁止借移除最后一个前缀放开限制
+        require!(
+            entry.allowed_prefixes.len() > 1,
+            MultisigError::CannotRemoveLastPrefix
+        );
+        // 移除前缀
+        entry.allowed_prefixes.retain(|p| p != &prefix);
+        // 递增配置代数，使按旧规则校验的提案失效
+        ms.bump_config_generation()?;
+        // 触发前缀移除事件
+        emit!(AllowedPrefixRemoved {
+            multisig: ms.key(),
+            program_id,
+            prefix
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 添加支出限额的指令，需通过提案执行
+    pub fn add_spending_limit(
+        ctx: Context<AddSpendingLimit>,
+        mint: Pubkey,              // 代币 mint，原生 SOL 使用默认公钥
+        amount: u64,               // 每个周期的额度
+        period: SpendingPeriod,    // 额度重置周期
+        members: Vec<Pubkey>,      // 可以使用额度的成员
+        destinations: Vec<Pubkey>, // 允许的目标地址，为空表示不限制（启用地址簿时不允许为空）
+    ) -> Result<()> {
+        // 获取多签账户
+        let ms = &ctx.accounts.multisig;
+        // 验证额度大于 0
+        require!(amount > 0, MultisigError::InvalidAmount);
+        // 验证成员列表非空、不超过最大限制且均为多签成员
+        require!(
+            !members.is_empty()
+                && members.len() <= MAX_OWNERS
+                && members.iter().all(|m| ms.is_member(m)),
+            MultisigError::InvalidSpendingLimit
+        );
+        // 验证目标地址数量不超过最大限制
+        require!(
+            destinations.len() <= MAX_SPENDING_LIMIT_DESTINATIONS,
+            MultisigError::InvalidSpendingLimit
+        );
+        // 验证成员和目标地址均无重复
+        let unique_members: HashSet<&Pubkey> = members.iter().collect();
+        let unique_destinations: HashSet<&Pubkey> = destinations.iter().collect();
+        require!(
+            unique_members.len() == members.len()
+                && unique_destinations.len() == destinations.len(),
+            MultisigError::InvalidSpendingLimit
+        );
+        // 启用地址簿时，目标地址必须显式列出且均在地址簿中，避免额度绕过地址簿
+        let address_book = &ctx.accounts.address_book;
+        require!(
+            !address_book.enabled
+                || (!destinations.is_empty()
+                    && destinations.iter().all(|d| address_book.allows(d))),
+            MultisigError::DestinationNotAllowed
+        );
+
+        // 初始化支出限额账户，首个周期从当前时间开始
+        let limit = &mut ctx.accounts.spending_limit;
+        limit.multisig = ms.key();
+        limit.mint = mint;
+        limit.amount = amount;
+        limit.period = period;
+        limit.remaining_amount = amount;
+        limit.last_reset = Clock::get()?.unix_timestamp;
+        limit.members = members;
+        limit.destinations = destinations;
+        limit.bump = ctx.bumps.spending_limit;
+
+        // 触发支出限额添加事件
+        emit!(SpendingLimitAdded {
+            multisig: ms.key(),
+            spending_limit: limit.key(),
+            mint,
+            amount,
+            period,
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 移除支出限额的指令，需通过提案执行，租金返还金库
+    pub fn remove_spending_limit(
+        ctx: Context<RemoveSpendingLimit>,
+        _mint: Pubkey, // 支出限额的 mint，用于推导支出限额 PDA
+    ) -> Result<()> {
+        // 触发支出限额移除事件
+        emit!(SpendingLimitRemoved {
+            multisig: ctx.accounts.multisig.key(),
+            spending_limit: ctx.accounts.spending_limit.key(),
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 在支出限额内由单个授权成员直接从金库转出资金的指令
+    pub fn spending_limit_use(ctx: Context<SpendingLimitUse>, amount: u64) -> Result<()> {
+        // 获取多签账户
+        let ms = &ctx.accounts.multisig;
+        // 确保多签账户未暂停
+        require!(!ms.paused, MultisigError::Paused);
+        // 验证金额大于 0
+        require!(amount > 0, MultisigError::InvalidAmount);
+
+        // 确保使用者仍是多签成员且被授权使用该额度
+        let member_key = ctx.accounts.member.key();
+        let limit = &mut ctx.accounts.spending_limit;
+        require!(
+            ms.is_member(&member_key) && limit.members.contains(&member_key),
+            MultisigError::SpendingLimitMemberNotAllowed
+        );
+        // 确保目标地址被允许
+        let destination = ctx.accounts.destination.key();
+        require!(
+            limit.allows_destination(&destination),
+            MultisigError::SpendingLimitDestinationNotAllowed
+        );
+        // 启用地址簿时，目标地址还需在地址簿中（额度可能在启用地址簿前创建，或目标已被移出地址簿）
+        require!(
+            ctx.accounts.address_book.allows(&destination),
+            MultisigError::DestinationNotAllowed
+        );
+
+        // 进入新周期时重置剩余额度，然后扣减本次金额
+        limit.refresh(Clock::get()?.unix_timestamp)?;
+        limit.remaining_amount = limit
+            .remaining_amount
+            .checked_sub(amount)
+            .ok_or(MultisigError::SpendingLimitExceeded)?;
+
+        // 设置金库的 PDA seed
+        let multisig_key = ms.key();
+        let bump_seed = [ms.vault_bump];
+        let seeds = &[b"vault".as_ref(), multisig_key.as_ref(), bump_seed.as_ref()];
+        let vault_seeds: &[&[&[u8]]] = &[seeds];
+
+        if limit.mint == Pubkey::default() {
+            // 原生 SOL：由金库签名通过系统程序转账
+            system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.vault.to_account_info(),
+                        to: ctx.accounts.destination.to_account_info(),
+                    },
+                    vault_seeds,
+                ),
+                amount,
+            )?;
+        } else {
+            // SPL 代币：需要提供 mint、金库代币账户、目标代币账户和代币程序
+            let (Some(mint), Some(from), Some(to), Some(token_program)) = (
+                &ctx.accounts.mint,
+                &ctx.accounts.vault_token_account,
+                &ctx.accounts.destination_token_account,
+                &ctx.accounts.token_program,
+            ) else {
+                return err!(MultisigError::InvalidSpendingLimitAccounts);
+            };
+            // 验证代币账户与额度的 mint、金库和目标地址一致
+            require!(
+                mint.key() == limit.mint
+                    && from.mint == limit.mint
+                    && from.owner == ctx.accounts.vault.key()
+                    && to.mint == limit.mint
+                    && to.owner == destination,
+                MultisigError::InvalidSpendingLimitAccounts
+            );
+            // 由金库签名转账代币
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: from.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                    vault_seeds,
+                ),
+                amount,
+                mint.decimals,
+            )?;
+        }
+
+        // 触发支出限额使用事件
+        emit!(SpendingLimitUsed {
+            multisig: multisig_key,
+            spending_limit: limit.key(),
+            member: member_key,
+            destination,
+            amount,
+            remaining_amount: limit.remaining_amount,
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 为金库创建关联代币账户的指令，需通过提案执行，租金由金库支付
+    pub fn create_vault_token_account(
+        ctx: Context<CreateVaultTokenAccount>,
+        _vault_index: u8, // 金库的索引，0 为旧版金库
+    ) -> Result<()> {
+        // 触发金库代币账户创建事件
+        emit!(VaultTokenAccountCreated {
+            multisig: ctx.accounts.multisig.key(),
+            mint: ctx.accounts.mint.key(),
+            token_account: ctx.accounts.vault_token_account.key(),
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 从金库代币账户转出代币的指令，需通过提案执行；
+    // 自动处理 Token-2022 的转账手续费，并在目标账户要求时先写入备注
+    pub fn vault_token_transfer(
+        ctx: Context<VaultTokenTransfer>,
+        _vault_index: u8,     // 金库的索引，0 为旧版金库
+        amount: u64,          // 转出数量
+        memo: Option<String>, // 转账备注
+    ) -> Result<()> {
+        // 验证金额大于 0
+        require!(amount > 0, MultisigError::InvalidAmount);
+        // 启用地址簿时，目标代币账户或其所有者必须在地址簿中（地址簿可能在提案后才启用）
+        let address_book = &ctx.accounts.address_book;
+        let destination = &ctx.accounts.destination_token_account;
+        require!(
+            address_book.allows(&destination.key()) || address_book.allows(&destination.owner),
+            MultisigError::DestinationNotAllowed
+        );
+
+        // 目标账户要求转入备注时，备注必须紧邻转账之前写入
+        let destination_info = ctx.accounts.destination_token_account.to_account_info();
+        let requires_memo =
+            StateWithExtensions::<TokenAccountState>::unpack(&destination_info.try_borrow_data()?)
+                .is_ok_and(|state| memo_required(&state));
+        match memo {
+            Some(memo) => memo::build_memo(
+                CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+                memo.as_bytes(),
+            )?,
+            None => require!(!requires_memo, MultisigError::MemoRequired),
+        }
+
+        // 金库已通过提案执行获得签名权限，直接转发给代币程序
+        let mint = &ctx.accounts.mint;
+        let fee = transfer_fee(&mint.to_account_info(), amount)?;
+        match fee {
+            // 配置了转账手续费的 Token-2022 代币，显式声明预期手续费
+            Some(fee) => token_interface::transfer_checked_with_fee(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferCheckedWithFee {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        source: ctx.accounts.vault_token_account.to_account_info(),
+                        mint: mint.to_account_info(),
+                        destination: ctx.accounts.destination_token_account.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                amount,
+                mint.decimals,
+                fee,
+            )?,
+            None => token_interface::transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.vault_token_account.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: ctx.accounts.destination_token_account.to_account_info(),
+                        authority: ctx.accounts.vault.to_account_info(),
+                    },
+                ),
+                amount,
+                mint.decimals,
+            )?,
+        }
+
+        // 触发金库代币转账事件
+        emit!(VaultTokenTransferred {
+            multisig: ctx.accounts.multisig.key(),
+            mint: mint.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+            fee: fee.unwrap_or(0),
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 关闭金库名下余额为 0 的代币账户的指令，需通过提案执行，租金返还金库
+    pub fn close_vault_token_account(
+        ctx: Context<CloseVaultTokenAccount>,
+        _vault_index: u8, // 金库的索引，0 为旧版金库
+    ) -> Result<()> {
+        // 由代币程序校验余额为 0 后关闭账户
+        token_interface::close_account(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vault_token_account.to_account_info(),
+                destination: ctx.accounts.vault.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+        ))?;
+        // 触发金库代币账户关闭事件
+        emit!(VaultTokenAccountClosed {
+            multisig: ctx.accounts.multisig.key(),
+            token_account: ctx.accounts.vault_token_account.key(),
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 启用或停用地址簿的指令
+    pub fn set_address_book_enabled(ctx: Context<ManageAddressBook>, enabled: bool) -> Result<()> {
+        // 获取多签账户
+        let ms = &mut ctx.accounts.multisig;
+        // 更新启用状态
+        ctx.accounts.address_book.enabled = enabled;
+        // 启用地址簿会收紧限制，递增配置代数使按旧规则校验的提案失效
+        if enabled {
+            ms.bump_config_generation()?;
+        }
+        // 触发地址簿启用状态变更事件
+        emit!(AddressBookToggled {
+            multisig: ms.key(),
+            enabled
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 添加地址到地址簿的指令
+    pub fn add_address_book_entry(ctx: Context<ManageAddressBook>, address: Pubkey) -> Result<()> {
+        // 获取地址簿账户
+        let address_book = &mut ctx.accounts.address_book;
+        // 确保地址未在地址簿中
+        require!(
+            !address_book.addresses.contains(&address),
+            MultisigError::AddressAlreadyInBook
+        );
+        // 确保地址簿未满
+        require!(
+            address_book.addresses.len() < MAX_ADDRESS_BOOK_SIZE,
+            MultisigError::AddressBookFull
+        );
+        // 添加地址
+        address_book.addresses.push(address);
+        // 触发地址添加事件
+        emit!(AddressBookEntryAdded {
             multisig: ctx.accounts.multisig.key(),
+            address
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 从地址簿移除地址的指令
+    pub fn remove_address_book_entry(
+        ctx: Context<ManageAddressBook>,
+        address: Pubkey,
+    ) -> Result<()> {
+        // 获取多签和地址簿账户
+        let ms = &mut ctx.accounts.multisig;
+        let address_book = &mut ctx.accounts.address_book;
+        // 记录当前地址簿长度
+        let old_len = address_book.addresses.len();
+        // 移除指定地址
+        address_book.addresses.retain(|a| a != &address);
+        // 确保地址被移除
+        require!(
+            address_book.addresses.len() < old_len,
+            MultisigError::AddressNotInBook
+        );
+        // 移除地址会收紧限制，递增配置代数使按旧规则校验的提案失效
+        ms.bump_config_generation()?;
+        // 触发地址移除事件
+        emit!(AddressBookEntryRemoved {
+            multisig: ms.key(),
+            address
+        });
+        // 返回成功
+        Ok(())
+    }
+
+    // 从白名单移除程序的指令
+    pub fn remove_from_whitelist(ctx: Context<ManageWhitelist>, program_id: Pubkey) -> Result<()> {
+        // 防止移除核心程序（系统程序和当前程序）
+        require!(
+            program_id != system_program::ID && program_id != crate::ID,
+            MultisigError::CannotRemoveCoreProgram
+        );
+        // 获取白名单账户
+        let whitelist = &mut ctx.accounts.whitelist;
+        // 记录当前白名单长度
+        let old_len = whitelist.programs.len();
+        // 移除指定程序
+        whitelist.programs.retain(|e| e.program_id != program_id);
+        // 确保程序被移除
+        require!(
+            whitelist.programs.len() < old_len,
+            MultisigError::ProgramNotFoundInWhitelist
+        );
+        // 递增配置代数，使调用该程序的未执行提案失效，而不是按基础阈值执行
+        let ms = &mut ctx.accounts.multisig;
+        ms.bump_config_generation()?;
+        // 触发白名单移除事件
+        emit!(WhitelistProgramRemoved {
+            multisig: ms.key(),
             program_id
         });
         // 返回成功
@@ -462,7 +1582,7 @@ pub mod multisig {
 
 // 定义创建多签账户的上下文
 #[derive(Accounts)]
-#[instruction(owners: Vec<Pubkey>, threshold: u8, nonce: u64)]
+#[instruction(owners: Vec<Member>, threshold: u16, nonce: u64)]
 pub struct CreateMultisig<'info> {
     // 初始化多签账户，分配空间并设置 PDA
     #[account(init, payer = payer, space = 8 + Multisig::INIT_SPACE, seeds = [b"multisig", payer.key().as_ref(), &nonce.to_le_bytes()], bump)]
@@ -474,6 +1594,9 @@ pub struct CreateMultisig<'info> {
     // 初始化白名单账户，分配空间并设置 PDA
     #[account(init, payer = payer, space = 8 + ProgramWhitelist::INIT_SPACE, seeds = [b"whitelist", multisig.key().as_ref()], bump)]
     pub whitelist: Account<'info, ProgramWhitelist>,
+    // 初始化地址簿账户，分配空间并设置 PDA
+    #[account(init, payer = payer, space = 8 + AddressBook::INIT_SPACE, seeds = [b"address_book", multisig.key().as_ref()], bump)]
+    pub address_book: Account<'info, AddressBook>,
     // 支付者账户，需签名
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -483,6 +1606,7 @@ pub struct CreateMultisig<'info> {
 
 // 定义提出交易的上下文
 #[derive(Accounts)]
+#[instruction(instructions: Vec<InstructionData>)]
 pub struct Propose<'info> {
     // 可变的多签账户
     #[account(mut)]
@@ -490,12 +1614,109 @@ pub struct Propose<'info> {
     // 白名单账户，验证程序 ID
     #[account(seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
     pub whitelist: Account<'info, ProgramWhitelist>,
-    // 金库账户，PDA
-    #[account(seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
-    /// CHECK: Vault is a PDA, seed check is enough.
+    // 签名金库账户，按 vault_index 在指令中验证地址
+    /// CHECK: Checked against the vault PDA derived from the vault index.
+    pub vault: UncheckedAccount<'info>,
+    // 地址簿账户，启用时验证可写账户
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 初始化交易账户，按实际指令列表分配空间并设置 PDA
+    #[account(init, payer = proposer, space = Transaction::space(&instructions), seeds = [b"tx", multisig.key().as_ref(), &multisig.next_tx_id.to_le_bytes()], bump)]
+    pub transaction: Account<'info, Transaction>,
+    // 提议者账户，需签名
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 定义转账快捷提案的上下文，交易账户按单条代币转账指令分配空间
+#[derive(Accounts)]
+pub struct ProposeTransfer<'info> {
+    // 可变的多签账户
+    #[account(mut)]
+    pub multisig: Account<'info, Multisig>,
+    // 白名单账户，验证程序 ID
+    #[account(seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
+    pub whitelist: Account<'info, ProgramWhitelist>,
+    // 签名金库账户，按 vault_index 在指令中验证地址
+    /// CHECK: Checked against the vault PDA derived from the vault index.
     pub vault: UncheckedAccount<'info>,
-    // 初始化交易账户，分配空间并设置 PDA
-    #[account(init, payer = proposer, space = 8 + Transaction::INIT_SPACE, seeds = [b"tx", multisig.key().as_ref(), &multisig.next_tx_id.to_le_bytes()], bump)]
+    // 地址簿账户，启用时验证可写账户
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 初始化交易账户，transfer_checked 指令含 4 个账户和 10 字节数据，足以容纳 SOL 转账
+    #[account(init, payer = proposer, space = Transaction::space_for_message(TransactionMessage::single_instruction_space(4, 10)), seeds = [b"tx", multisig.key().as_ref(), &multisig.next_tx_id.to_le_bytes()], bump)]
+    pub transaction: Account<'info, Transaction>,
+    // 提议者账户，需签名
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 定义创建交易缓冲区的上下文
+#[derive(Accounts)]
+#[instruction(buffer_index: u8, vault_index: u8, final_hash: [u8; 32], final_size: u32)]
+pub struct CreateTransactionBuffer<'info> {
+    // 多签账户，并验证声明的完整长度
+    #[account(constraint = final_size as usize <= MAX_TRANSACTION_BUFFER_SIZE @ MultisigError::TransactionBufferTooLarge)]
+    pub multisig: Account<'info, Multisig>,
+    // 初始化交易缓冲区，按完整长度分配空间
+    #[account(init, payer = creator, space = TransactionBuffer::space(final_size), seeds = [b"transaction_buffer", multisig.key().as_ref(), creator.key().as_ref(), &[buffer_index]], bump)]
+    pub transaction_buffer: Account<'info, TransactionBuffer>,
+    // 创建者账户，需签名并支付租金
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    // 系统程序
+    pub system_program: Program<'info, System>,
+}
+
+// 定义追加交易缓冲区数据的上下文
+#[derive(Accounts)]
+pub struct ExtendTransactionBuffer<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 可变的交易缓冲区，需关联多签账户和创建者
+    #[account(mut, has_one = multisig, has_one = creator)]
+    pub transaction_buffer: Account<'info, TransactionBuffer>,
+    // 创建者账户，需签名
+    pub creator: Signer<'info>,
+}
+
+// 定义关闭交易缓冲区的上下文
+#[derive(Accounts)]
+pub struct CloseTransactionBuffer<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 交易缓冲区，关闭后租金退还给创建者
+    #[account(mut, has_one = multisig, has_one = creator, close = creator)]
+    pub transaction_buffer: Account<'info, TransactionBuffer>,
+    // 创建者账户，需签名
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+// 定义从交易缓冲区提出交易的上下文
+#[derive(Accounts)]
+pub struct ProposeFromBuffer<'info> {
+    // 可变的多签账户
+    #[account(mut)]
+    pub multisig: Account<'info, Multisig>,
+    // 白名单账户，验证程序 ID
+    #[account(seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
+    pub whitelist: Account<'info, ProgramWhitelist>,
+    // 签名金库账户，按缓冲区记录的 vault_index 在指令中验证地址
+    /// CHECK: Checked against the vault PDA derived from the vault index.
+    pub vault: UncheckedAccount<'info>,
+    // 地址簿账户，启用时验证可写账户
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 交易缓冲区，需由提议者创建，转换后关闭并退还租金
+    #[account(mut, has_one = multisig, constraint = transaction_buffer.creator == proposer.key() @ MultisigError::NotTransactionBufferCreator, close = proposer)]
+    pub transaction_buffer: Account<'info, TransactionBuffer>,
+    // 初始化交易账户，按缓冲区内容分配空间
+    #[account(init, payer = proposer, space = Transaction::space_for_message(transaction_buffer.buffer.len()), seeds = [b"tx", multisig.key().as_ref(), &multisig.next_tx_id.to_le_bytes()], bump)]
     pub transaction: Account<'info, Transaction>,
     // 提议者账户，需签名
     #[account(mut)]
@@ -509,22 +1730,50 @@ pub struct Propose<'info> {
 pub struct Approve<'info> {
     // 多签账户
     pub multisig: Account<'info, Multisig>,
+    // 白名单账户，提供各程序的风险等级阈值
+    #[account(seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
+    pub whitelist: Account<'info, ProgramWhitelist>,
     // 可变的交易账户，需关联多签账户
     #[account(mut, has_one = multisig)]
     pub transaction: Account<'info, Transaction>,
     // 批准者账户，需是 owner 之一
-    #[account(constraint = multisig.owners.contains(&owner.key()) @ MultisigError::NotAnOwner)]
+    #[account(constraint = multisig.is_member(&owner.key()) @ MultisigError::NotAnOwner)]
     pub owner: Signer<'info>,
 }
 
+// 定义刷新过时提案的上下文
+#[derive(Accounts)]
+pub struct RefreshProposal<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 白名单账户，提供各程序的风险等级阈值并重新验证指令
+    #[account(seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
+    pub whitelist: Account<'info, ProgramWhitelist>,
+    // 地址簿账户，启用时重新验证可写账户
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 可变的交易账户，需关联多签和提议者
+    #[account(mut, has_one = multisig, has_one = proposer)]
+    pub transaction: Account<'info, Transaction>,
+    // 提议者账户，需签名且仍拥有提案权限
+    #[account(constraint = multisig.has_permission(&proposer.key(), PERMISSION_PROPOSE) @ MultisigError::MissingPermission)]
+    pub proposer: Signer<'info>,
+}
+
 // 定义执行交易的上下文
 #[derive(Accounts)]
 pub struct Execute<'info> {
     // 多签账户
     pub multisig: Account<'info, Multisig>,
+    // 白名单账户，提供各程序的风险等级阈值
+    #[account(seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
+    pub whitelist: Account<'info, ProgramWhitelist>,
     // 可变的交易账户，需关联多签账户
     #[account(mut, has_one = multisig)]
     pub transaction: Account<'info, Transaction>,
+    // 执行者账户，需签名且符合多签的执行者策略
+    #[account(constraint = multisig.can_execute(&executor.key()) @ MultisigError::ExecutorNotAllowed)]
+    pub executor: Signer<'info>,
 }
 
 // 定义管理多签账户的上下文
@@ -541,7 +1790,8 @@ pub struct Manage<'info> {
 // 定义管理白名单的上下文
 #[derive(Accounts)]
 pub struct ManageWhitelist<'info> {
-    // 多签账户
+    // 可变的多签账户
+    #[account(mut)]
     pub multisig: Account<'info, Multisig>,
     // 可变的白名单账户
     #[account(mut, seeds = [b"whitelist", multisig.key().as_ref()], bump = multisig.whitelist_bump)]
@@ -551,21 +1801,166 @@ pub struct ManageWhitelist<'info> {
     pub vault: Signer<'info>,
 }
 
+// 定义添加支出限额的上下文
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct AddSpendingLimit<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 地址簿账户，PDA，启用时限制额度的目标地址
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 初始化支出限额账户，每个多签每种 mint 一个，租金由金库支付
+    #[account(init, payer = vault, space = 8 + SpendingLimit::INIT_SPACE, seeds = [b"spending_limit", multisig.key().as_ref(), mint.as_ref()], bump)]
+    pub spending_limit: Account<'info, SpendingLimit>,
+    // 金库账户，需签名并支付租金
+    #[account(mut, seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: Signer<'info>,
+    // 系统程序，用于账户创建
+    pub system_program: Program<'info, System>,
+}
+
+// 定义移除支出限额的上下文
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RemoveSpendingLimit<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 可变的支出限额账户，PDA，关闭后租金返还金库
+    #[account(mut, has_one = multisig, seeds = [b"spending_limit", multisig.key().as_ref(), mint.as_ref()], bump = spending_limit.bump, close = vault)]
+    pub spending_limit: Account<'info, SpendingLimit>,
+    // 金库账户，需签名
+    #[account(mut, seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: Signer<'info>,
+}
+
+// 定义使用支出限额的上下文
+#[derive(Accounts)]
+pub struct SpendingLimitUse<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 地址簿账户，PDA，启用时限制额度的目标地址
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 可变的支出限额账户，需关联多签账户
+    #[account(mut, has_one = multisig, seeds = [b"spending_limit", multisig.key().as_ref(), spending_limit.mint.as_ref()], bump = spending_limit.bump)]
+    pub spending_limit: Account<'info, SpendingLimit>,
+    // 使用额度的成员，需签名
+    pub member: Signer<'info>,
+    // 金库账户，PDA
+    #[account(mut, seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: SystemAccount<'info>,
+    // 目标地址，在指令中按支出限额的目标列表验证
+    #[account(mut)]
+    /// CHECK: Checked against the spending limit's destinations.
+    pub destination: UncheckedAccount<'info>,
+    // 系统程序，用于原生 SOL 转账
+    pub system_program: Program<'info, System>,
+    // 代币 mint（仅 SPL 代币额度需要）
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    // 金库的代币账户（仅 SPL 代币额度需要）
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    // 目标地址的代币账户（仅 SPL 代币额度需要）
+    #[account(mut)]
+    pub destination_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    // 代币程序（仅 SPL 代币额度需要）
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+// 定义创建金库代币账户的上下文
+#[derive(Accounts)]
+#[instruction(vault_index: u8)]
+pub struct CreateVaultTokenAccount<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 所选索引的金库账户，需签名并支付租金
+    #[account(mut, address = multisig.vault_address(&multisig.key(), vault_index)?.0 @ MultisigError::InvalidVault)]
+    pub vault: Signer<'info>,
+    // 代币 mint
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    // 初始化金库的关联代币账户
+    #[account(init, payer = vault, associated_token::mint = mint, associated_token::authority = vault, associated_token::token_program = token_program)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    // 代币程序（Token 或 Token-2022）
+    pub token_program: Interface<'info, TokenInterface>,
+    // 关联代币账户程序
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    // 系统程序，用于账户创建
+    pub system_program: Program<'info, System>,
+}
+
+// 定义金库代币转账的上下文
+#[derive(Accounts)]
+#[instruction(vault_index: u8)]
+pub struct VaultTokenTransfer<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 地址簿账户，PDA，启用时限制转账的目标地址
+    #[account(seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 所选索引的金库账户，需签名
+    #[account(address = multisig.vault_address(&multisig.key(), vault_index)?.0 @ MultisigError::InvalidVault)]
+    pub vault: Signer<'info>,
+    // 代币 mint
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    // 金库名下的源代币账户
+    #[account(mut, token::mint = mint, token::authority = vault, token::token_program = token_program)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    // 目标代币账户
+    #[account(mut, token::mint = mint, token::token_program = token_program)]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    // 代币程序（Token 或 Token-2022）
+    pub token_program: Interface<'info, TokenInterface>,
+    // Memo 程序，用于写入转账备注
+    pub memo_program: Program<'info, Memo>,
+}
+
+// 定义关闭金库代币账户的上下文
+#[derive(Accounts)]
+#[instruction(vault_index: u8)]
+pub struct CloseVaultTokenAccount<'info> {
+    // 多签账户
+    pub multisig: Account<'info, Multisig>,
+    // 所选索引的金库账户，需签名并接收返还的租金
+    #[account(mut, address = multisig.vault_address(&multisig.key(), vault_index)?.0 @ MultisigError::InvalidVault)]
+    pub vault: Signer<'info>,
+    // 金库名下待关闭的代币账户
+    #[account(mut, token::authority = vault, token::token_program = token_program)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    // 代币程序（Token 或 Token-2022）
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// 定义管理地址簿的上下文
+#[derive(Accounts)]
+pub struct ManageAddressBook<'info> {
+    // 可变的多签账户
+    #[account(mut)]
+    pub multisig: Account<'info, Multisig>,
+    // 可变的地址簿账户
+    #[account(mut, seeds = [b"address_book", multisig.key().as_ref()], bump = multisig.address_book_bump)]
+    pub address_book: Account<'info, AddressBook>,
+    // 金库账户，需签名
+    #[account(seeds = [b"vault", multisig.key().as_ref()], bump = multisig.vault_bump)]
+    pub vault: Signer<'info>,
+}
+
 // 定义取消提案的上下文
 #[derive(Accounts)]
 pub struct CancelProposal<'info> {
     // 多签账户
     pub multisig: Account<'info, Multisig>,
-    // 可变的交易账户，需关联多签和提议者，关闭后租金返还
+    // 可变的交易账户，需关联多签和提议者
     #[account(
         mut,
         has_one = multisig,
         has_one = proposer, // 签名者需是原提议者
-        close = proposer // 关闭账户，租金返还给提议者
     )]
     pub transaction: Account<'info, Transaction>,
     // 提议者账户，需签名
-    #[account(mut)]
     pub proposer: Signer<'info>,
     // 系统程序（注释掉，未使用）
     // pub system_program: Program<'info, System>,
@@ -576,63 +1971,444 @@ pub struct CancelProposal<'info> {
 pub struct CloseTransaction<'info> {
     // 多签账户
     pub multisig: Account<'info, Multisig>,
-    // 可变的交易账户，需已执行或过期，关闭后租金返还
-    #[account(
-        mut,
-        has_one = multisig,
-        constraint = transaction.executed || transaction.is_expired() @ MultisigError::TransactionNotClosable,
-        close = recipient
-    )]
+    // 可变的交易账户，需处于终结状态，关闭后租金返还给支付租金的提议者
+    #[account(mut, has_one = multisig, has_one = proposer, close = proposer)]
     pub transaction: Account<'info, Transaction>,
-    // 租金接收者账户，需签名
+    // 提议者账户，接收返还的租金
     #[account(mut)]
-    pub recipient: Signer<'info>,
+    pub proposer: SystemAccount<'info>,
+    // 关闭者账户，需签名
+    pub closer: Signer<'info>,
     // 验证关闭权限，需是 owner 或提议者
     #[account(
-        constraint = multisig.owners.contains(&recipient.key()) || transaction.proposer == recipient.key()
+        constraint = multisig.is_member(&closer.key()) || transaction.proposer == closer.key()
         @ MultisigError::ClosePermissionDenied
     )]
     /// CHECK: This is the signer who is authorized to close the transaction.
     pub authorized_closer: UncheckedAccount<'info>,
 }
 
-// 定义多签账户的数据结构
-#[account]
-#[derive(InitSpace)]
-pub struct Multisig {
-    pub bump: u8,           // 多签 PDA 的 bump seed
-    pub vault_bump: u8,     // 金库 PDA 的 bump seed
-    pub whitelist_bump: u8, // 白名单 PDA 的 bump seed
-    #[max_len(MAX_OWNERS)]
-    pub owners: Vec<Pubkey>, // owner 公钥列表
-    pub threshold: u8,      // 批准阈值
-    pub next_tx_id: u64,    // 下一个交易 ID
-    pub paused: bool,       // 暂停状态
-    pub nonce: u64,         // PDA 随机数
+// 定义多签账户的数据结构
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    pub bump: u8,           // 多签 PDA 的 bump seed
+    pub vault_bump: u8,     // 金库 PDA 的 bump seed
+    pub whitelist_bump: u8, // 白名单 PDA 的 bump seed
+    #[max_len(MAX_OWNERS)]
+    pub owners: Vec<Member>, // owner 成员列表（公钥及权限）
+    pub threshold: u16,     // 批准阈值（投票权重之和）
+    pub next_tx_id: u64,    // 下一个交易 ID
+    pub paused: bool,       // 暂停状态
+    pub nonce: u64,         // PDA 随机数
+    pub config_generation: u32, // 配置代数，owner 集合或阈值变更时递增
+    pub time_lock_seconds: u32, // 达到阈值后到可执行前的延迟（秒），0 表示不启用
+    pub executor_policy: ExecutorPolicy, // 执行者策略
+    #[max_len(MAX_EXECUTORS)]
+    pub executors: Vec<Pubkey>, // 指定执行者列表（仅 Designated 策略使用）
+    pub config_threshold: u16, // 调用本程序（配置变更）的提案所需的投票权重之和
+    pub address_book_bump: u8, // 地址簿 PDA 的 bump seed
+    pub guard_program: Option<Pubkey>, // 执行前后调用的 guard 程序（可选）
+}
+
+// 实现多签账户的辅助方法
+impl Multisig {
+    // 查找指定公钥对应的成员
+    pub fn member(&self, key: &Pubkey) -> Option<&Member> {
+        self.owners.iter().find(|m| m.key == *key)
+    }
+
+    // 检查公钥是否为成员
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.member(key).is_some()
+    }
+
+    // 检查成员是否拥有指定权限
+    pub fn has_permission(&self, key: &Pubkey, permission: u8) -> bool {
+        self.member(key)
+            .is_some_and(|m| m.has_permission(permission))
+    }
+
+    // 检查公钥是否可以按执行者策略执行提案
+    pub fn can_execute(&self, key: &Pubkey) -> bool {
+        match self.executor_policy {
+            ExecutorPolicy::Anyone => true,
+            ExecutorPolicy::OwnersOnly => self.has_permission(key, PERMISSION_EXECUTE),
+            ExecutorPolicy::Designated => self.executors.contains(key),
+        }
+    }
+
+    // 统计拥有投票权限的成员权重之和
+    pub fn total_vote_weight(&self) -> u32 {
+        total_vote_weight(&self.owners)
+    }
+
+    // 统计投票（批准或拒绝）列表中仍拥有投票权限的成员权重之和
+    pub fn vote_weight(&self, votes: &[Pubkey]) -> u32 {
+        votes
+            .iter()
+            .filter_map(|key| self.member(key))
+            .filter(|m| m.has_permission(PERMISSION_VOTE))
+            .map(|m| m.weight as u32)
+            .sum()
+    }
+
+    // 计算提案适用的阈值：任一指令调用本程序时取配置变更阈值，
+    // 并取所调用程序风险等级阈值中的最大者，均不低于批准阈值；
+    // 风险等级阈值不超过当前总投票权重，避免移除 owner 或降低权重后无法通过任何提案
+    pub fn threshold_for(&self, whitelist: &ProgramWhitelist, program_ids: &[Pubkey]) -> u16 {
+        let total_weight = u16::try_from(self.total_vote_weight()).unwrap_or(u16::MAX);
+        program_ids
+            .iter()
+            .map(|program_id| {
+                let tier = whitelist.required_approvals(program_id).min(total_weight);
+                if *program_id == crate::ID {
+                    tier.max(self.config_threshold)
+                } else {
+                    tier
+                }
+            })
+            .fold(self.threshold, u16::max)
+    }
+
+    // 检查投票权重是否达到阈值
+    pub fn meets_threshold(&self, votes: &[Pubkey], threshold: u16) -> bool {
+        self.vote_weight(votes) >= threshold as u32
+    }
+
+    // 检查在给定拒绝票下，剩余投票成员是否仍可能达到阈值
+    pub fn can_reach_threshold(&self, rejections: &[Pubkey], threshold: u16) -> bool {
+        self.total_vote_weight() - self.vote_weight(rejections) >= threshold as u32
+    }
+
+    // 推导指定索引的金库地址及 bump：索引 0 为旧版金库 [b"vault", multisig]，
+    // 其余为 [b"vault", multisig, index]
+    pub fn vault_address(&self, multisig_key: &Pubkey, index: u8) -> Result<(Pubkey, u8)> {
+        if index == 0 {
+            let address = Pubkey::create_program_address(
+                &[b"vault", multisig_key.as_ref(), &[self.vault_bump]],
+                &crate::ID,
+            )
+            .map_err(|_| MultisigError::InvalidVault)?;
+            Ok((address, self.vault_bump))
+        } else {
+            Ok(Pubkey::find_program_address(
+                &[b"vault", multisig_key.as_ref(), &[index]],
+                &crate::ID,
+            ))
+        }
+    }
+
+    // 递增配置代数，使基于旧配置的提案失效
+    pub fn bump_config_generation(&mut self) -> Result<()> {
+        self.config_generation = self
+            .config_generation
+            .checked_add(1)
+            .ok_or(MultisigError::Overflow)?;
+        Ok(())
+    }
+}
+
+// 定义执行者策略
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ExecutorPolicy {
+    Anyone,     // 任何人都可以执行
+    OwnersOnly, // 仅拥有执行权限的成员可以执行
+    Designated, // 仅指定执行者列表中的账户可以执行
+}
+
+// 定义提案的执行方式
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ExecutionMode {
+    Atomic,   // 在一笔交易内执行全部指令
+    Stepwise, // 跨多笔交易逐条执行指令
+}
+
+// 定义提案执行后需成立的余额断言，防止指令的实际效果与描述不符
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum BalanceAssertion {
+    // 账户的 lamports 减少量不超过上限
+    MaxLamportDecrease {
+        account: Pubkey,
+        max_decrease: u64,
+    },
+    // 代币账户执行后的余额不低于下限
+    MinTokenBalance {
+        token_account: Pubkey,
+        min_amount: u64,
+    },
+}
+
+// 实现余额断言的辅助方法
+impl BalanceAssertion {
+    // 断言检查的账户
+    pub fn account(&self) -> &Pubkey {
+        match self {
+            BalanceAssertion::MaxLamportDecrease { account, .. } => account,
+            BalanceAssertion::MinTokenBalance { token_account, .. } => token_account,
+        }
+    }
+
+    // 读取断言关注的余额：lamports 或代币数量
+    fn balance(&self, info: &AccountInfo) -> Result<u64> {
+        match self {
+            BalanceAssertion::MaxLamportDecrease { .. } => Ok(info.lamports()),
+            BalanceAssertion::MinTokenBalance { .. } => {
+                require!(
+                    info.owner == &token::ID || info.owner == &token_2022::ID,
+                    MultisigError::InvalidBalanceAssertion
+                );
+                let data = info.try_borrow_data()?;
+                let state = StateWithExtensions::<TokenAccountState>::unpack(&data)
+                    .map_err(|_| MultisigError::InvalidBalanceAssertion)?;
+                Ok(state.base.amount)
+            }
+        }
+    }
+
+    // 根据执行前后的余额判断断言是否成立
+    pub fn holds(&self, before: u64, after: u64) -> bool {
+        match *self {
+            BalanceAssertion::MaxLamportDecrease { max_decrease, .. } => {
+                before.saturating_sub(after) <= max_decrease
+            }
+            BalanceAssertion::MinTokenBalance { min_amount, .. } => after >= min_amount,
+        }
+    }
+}
+
+// 定义多签成员的数据结构
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub struct Member {
+    pub key: Pubkey,     // 成员公钥
+    pub permissions: u8, // 权限位（提案、投票、执行）
+    pub weight: u16,     // 投票权重
+}
+
+// 实现多签成员的辅助方法
+impl Member {
+    // 检查成员是否拥有指定权限
+    pub fn has_permission(&self, permission: u8) -> bool {
+        self.permissions & permission == permission
+    }
 }
 
 // 定义交易账户的数据结构
 #[account]
 #[derive(InitSpace)]
 pub struct Transaction {
-    pub multisig: Pubkey, // 关联的多签账户
-    pub id: u64,          // 交易 ID
-    pub proposer: Pubkey, // 提议者公钥
-    #[max_len(MAX_IX_PER_TX, InstructionData::INIT_SPACE)]
-    pub instructions: Vec<InstructionData>, // 指令列表
+    pub multisig: Pubkey,            // 关联的多签账户
+    pub id: u64,                     // 交易 ID
+    pub proposer: Pubkey,            // 提议者公钥
+    pub message: TransactionMessage, // 紧凑格式的指令列表
     #[max_len(MAX_OWNERS)]
     pub approvals: Vec<Pubkey>, // 批准者列表
-    pub executed: bool,   // 是否已执行
-    pub expires_at: Option<i64>, // 过期时间
+    #[max_len(MAX_OWNERS)]
+    pub rejections: Vec<Pubkey>, // 拒绝者列表
+    pub expires_at: Option<i64>,     // 过期时间
+    pub config_generation: u32,      // 提案时多签的配置代数
+    pub status: ProposalStatus,      // 提案状态
+    pub timestamps: ProposalTimestamps, // 各状态迁移的时间
+    pub vault_index: u8,             // 签名金库的索引，0 为旧版金库
+    pub vault_bump: u8,              // 签名金库的 bump seed
+    #[max_len(MAX_EPHEMERAL_SIGNERS)]
+    pub ephemeral_signer_bumps: Vec<u8>, // 临时签名者的 bump seed，按索引排列
+    pub execution_mode: ExecutionMode, // 执行方式
+    pub next_ix_index: u16,          // 分步执行时下一条待执行指令的索引
+    #[max_len(MAX_BALANCE_ASSERTIONS)]
+    pub assertions: Vec<BalanceAssertion>, // 执行后需成立的余额断言
+}
+
+// 定义交易缓冲区账户的数据结构，按声明的完整长度分配空间
+#[account]
+#[derive(InitSpace)]
+pub struct TransactionBuffer {
+    pub multisig: Pubkey,     // 关联的多签账户
+    pub creator: Pubkey,      // 创建者公钥
+    pub buffer_index: u8,     // 缓冲区索引
+    pub vault_index: u8,      // 签名金库的索引
+    pub final_hash: [u8; 32], // 完整内容的 SHA-256 哈希
+    pub final_size: u32,      // 完整内容的长度（字节）
+    #[max_len(0)]
+    pub buffer: Vec<u8>, // 已上传的序列化紧凑消息
+}
+
+// 实现交易缓冲区的辅助方法
+impl TransactionBuffer {
+    // 计算容纳指定完整长度的缓冲区账户空间
+    pub fn space(final_size: u32) -> usize {
+        8 + Self::INIT_SPACE + final_size as usize
+    }
+
+    // 验证缓冲区已上传完整且内容与声明的哈希一致
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.buffer.len() == self.final_size as usize,
+            MultisigError::TransactionBufferIncomplete
+        );
+        require!(
+            hash(&self.buffer).to_bytes() == self.final_hash,
+            MultisigError::TransactionBufferHashMismatch
+        );
+        Ok(())
+    }
 }
 
 // 实现交易账户的辅助方法
 impl Transaction {
-    // 检查交易是否过期
-    pub fn is_expired(&self) -> bool {
-        self.expires_at
-            .map_or(false, |exp| Clock::get().unwrap().unix_timestamp > exp)
+    // 计算存放指定长度紧凑消息（序列化后字节数）的交易账户空间，而非按最大指令数分配
+    pub fn space_for_message(message_len: usize) -> usize {
+        8 + Self::INIT_SPACE - TransactionMessage::INIT_SPACE + message_len
     }
+
+    // 计算存放给定指令列表编译后消息的交易账户空间；无法编译时由处理函数报错
+    pub fn space(instructions: &[InstructionData]) -> usize {
+        let message_len =
+            TransactionMessage::compile(instructions).map_or(0, |m| m.serialized_len());
+        Self::space_for_message(message_len)
+    }
+
+    // 检查提案是否仅包含一条恢复（取消暂停）指令
+    pub fn is_resume_proposal(&self) -> Result<bool> {
+        Ok(match self.message.instructions.as_slice() {
+            [ix] => is_pause_instruction(
+                &self.message.key(ix.program_id_index)?,
+                &ix.data,
+                &crate::ID,
+                false,
+            ),
+            _ => false,
+        })
+    }
+
+    // 检查提案是否仅包含一条设置 guard 程序的指令，此类提案不经过 guard 钩子，
+    // 避免出错的 guard 程序阻止自身被替换或清除
+    pub fn is_guard_update_proposal(&self) -> Result<bool> {
+        Ok(match self.message.instructions.as_slice() {
+            [ix] => {
+                self.message.key(ix.program_id_index)? == crate::ID
+                    && ix
+                        .data
+                        .starts_with(crate::instruction::SetGuardProgram::DISCRIMINATOR)
+            }
+            _ => false,
+        })
+    }
+
+    // 检查提案是否仍处于投票阶段
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self.status,
+            ProposalStatus::Active | ProposalStatus::Approved
+        )
+    }
+
+    // 确保提案处于投票阶段，否则返回对应的错误
+    pub fn require_pending(&self) -> Result<()> {
+        match self.status {
+            ProposalStatus::Active | ProposalStatus::Approved => Ok(()),
+            ProposalStatus::Executing | ProposalStatus::Executed => {
+                err!(MultisigError::AlreadyExecuted)
+            }
+            ProposalStatus::Rejected => err!(MultisigError::ProposalRejected),
+            _ => err!(MultisigError::ProposalNotActive),
+        }
+    }
+
+    // 确保提案已获批准，可以执行
+    pub fn require_approved(&self) -> Result<()> {
+        match self.status {
+            ProposalStatus::Approved => Ok(()),
+            ProposalStatus::Active => err!(MultisigError::NotEnoughApprovals),
+            _ => self.require_pending(),
+        }
+    }
+
+    // 迁移提案状态并记录时间，所有状态变更都经由此处
+    pub fn transition_to(&mut self, next: ProposalStatus, now: i64) -> Result<()> {
+        require!(
+            self.status.can_transition_to(next),
+            MultisigError::InvalidStatusTransition
+        );
+        let ts = &mut self.timestamps;
+        match next {
+            ProposalStatus::Draft => ts.created_at = now,
+            // 从已批准回到投票阶段时清除批准时间，时间锁需重新计时
+            ProposalStatus::Active if self.status == ProposalStatus::Approved => {
+                ts.approved_at = None
+            }
+            ProposalStatus::Active => ts.activated_at = Some(now),
+            ProposalStatus::Approved => ts.approved_at = Some(now),
+            ProposalStatus::Rejected => ts.rejected_at = Some(now),
+            ProposalStatus::Executing => ts.executing_at = Some(now),
+            ProposalStatus::Executed => ts.executed_at = Some(now),
+            ProposalStatus::Cancelled => ts.cancelled_at = Some(now),
+            ProposalStatus::Expired => ts.expired_at = Some(now),
+        }
+        self.status = next;
+        Ok(())
+    }
+}
+
+// 定义提案状态
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum ProposalStatus {
+    Draft,     // 草稿，尚未开放投票
+    Active,    // 投票中
+    Approved,  // 已达到阈值，等待执行
+    Rejected,  // 已被拒绝
+    Executing, // 执行中
+    Executed,  // 已执行
+    Cancelled, // 已取消
+    Expired,   // 已过期
+}
+
+// 实现提案状态的迁移规则
+impl ProposalStatus {
+    // 检查是否允许从当前状态迁移到目标状态
+    pub fn can_transition_to(self, next: ProposalStatus) -> bool {
+        use ProposalStatus::*;
+        matches!(
+            (self, next),
+            (Draft, Active)
+                | (Draft, Cancelled)
+                | (Active, Approved)
+                | (Active, Rejected)
+                | (Active, Cancelled)
+                | (Active, Expired)
+                | (Approved, Active)
+                | (Approved, Executing)
+                | (Approved, Executed)
+                | (Approved, Cancelled)
+                | (Approved, Expired)
+                | (Executing, Executed)
+                | (Executing, Cancelled)
+        )
+    }
+
+    // 检查是否为终结状态，终结状态的提案可以关闭
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            ProposalStatus::Rejected
+                | ProposalStatus::Executed
+                | ProposalStatus::Cancelled
+                | ProposalStatus::Expired
+        )
+    }
+}
+
+// 定义提案各状态迁移的时间
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct ProposalTimestamps {
+    pub created_at: i64,           // 创建时间
+    pub activated_at: Option<i64>, // 开放投票时间
+    pub approved_at: Option<i64>,  // 达到阈值时间
+    pub rejected_at: Option<i64>,  // 被拒绝时间
+    pub executing_at: Option<i64>, // 开始执行时间
+    pub executed_at: Option<i64>,  // 执行完成时间
+    pub cancelled_at: Option<i64>, // 取消时间
+    pub expired_at: Option<i64>,   // 过期时间
 }
 
 // 定义白名单账户的数据结构
@@ -640,7 +2416,142 @@ impl Transaction {
 #[derive(InitSpace)]
 pub struct ProgramWhitelist {
     #[max_len(MAX_WHITELIST_SIZE)]
-    pub programs: Vec<Pubkey>, // 白名单程序列表
+    pub programs: Vec<WhitelistEntry>, // 白名单程序列表（含风险等级阈值）
+}
+
+// 实现白名单账户的辅助方法
+impl ProgramWhitelist {
+    // 检查程序是否在白名单中
+    pub fn contains(&self, program_id: &Pubkey) -> bool {
+        self.programs.iter().any(|e| e.program_id == *program_id)
+    }
+
+    // 检查指令数据是否匹配程序允许的指令前缀，前缀列表为空时不限制
+    pub fn allows_data(&self, program_id: &Pubkey, data: &[u8]) -> bool {
+        self.programs
+            .iter()
+            .find(|e| e.program_id == *program_id)
+            .is_some_and(|e| {
+                e.allowed_prefixes.is_empty()
+                    || e.allowed_prefixes.iter().any(|p| data.starts_with(p))
+            })
+    }
+
+    // 获取程序的风险等级阈值，不在白名单中时为 0
+    pub fn required_approvals(&self, program_id: &Pubkey) -> u16 {
+        self.programs
+            .iter()
+            .find(|e| e.program_id == *program_id)
+            .map_or(0, |e| e.required_approvals)
+    }
+}
+
+// 定义白名单条目的数据结构
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug, InitSpace)]
+pub struct WhitelistEntry {
+    pub program_id: Pubkey,      // 程序 ID
+    pub required_approvals: u16, // 调用该程序的提案所需的投票权重之和，0 表示使用批准阈值
+    #[max_len(MAX_PREFIXES_PER_PROGRAM, MAX_PREFIX_LEN)]
+    pub allowed_prefixes: Vec<Vec<u8>>, // 允许的指令数据前缀，为空表示允许该程序的所有指令
+}
+
+// 实现白名单条目的辅助方法
+impl WhitelistEntry {
+    // 创建不额外提高阈值的白名单条目
+    pub fn new(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            required_approvals: 0,
+            allowed_prefixes: vec![],
+        }
+    }
+}
+
+// 定义地址簿账户的数据结构
+#[account]
+#[derive(InitSpace)]
+pub struct AddressBook {
+    pub enabled: bool, // 是否启用地址簿限制
+    #[max_len(MAX_ADDRESS_BOOK_SIZE)]
+    pub addresses: Vec<Pubkey>, // 允许标记为可写的目标地址列表
+}
+
+// 实现地址簿账户的辅助方法
+impl AddressBook {
+    // 检查地址是否被允许作为转账目标，未启用时不限制
+    pub fn allows(&self, address: &Pubkey) -> bool {
+        !self.enabled || self.addresses.contains(address)
+    }
+
+    // 检查指令中的可写账户是否均被允许，未启用时不限制
+    pub fn allows_writable(&self, ix: &InstructionData, own_accounts: &[Pubkey]) -> bool {
+        !self.enabled
+            || ix.accounts.iter().filter(|acc| acc.is_writable).all(|acc| {
+                own_accounts.contains(&acc.pubkey) || self.addresses.contains(&acc.pubkey)
+            })
+    }
+}
+
+// 定义支出限额账户的数据结构
+#[account]
+#[derive(InitSpace)]
+pub struct SpendingLimit {
+    pub multisig: Pubkey,       // 关联的多签账户
+    pub mint: Pubkey,           // 代币 mint，原生 SOL 为默认公钥
+    pub amount: u64,            // 每个周期的额度
+    pub period: SpendingPeriod, // 额度重置周期
+    pub remaining_amount: u64,  // 当前周期剩余额度
+    pub last_reset: i64,        // 当前周期的开始时间
+    #[max_len(MAX_OWNERS)]
+    pub members: Vec<Pubkey>, // 可以使用额度的成员
+    #[max_len(MAX_SPENDING_LIMIT_DESTINATIONS)]
+    pub destinations: Vec<Pubkey>, // 允许的目标地址，为空表示不限制
+    pub bump: u8,               // 支出限额 PDA 的 bump seed
+}
+
+// 实现支出限额账户的辅助方法
+impl SpendingLimit {
+    // 检查目标地址是否被允许
+    pub fn allows_destination(&self, destination: &Pubkey) -> bool {
+        self.destinations.is_empty() || self.destinations.contains(destination)
+    }
+
+    // 若已进入新周期，重置剩余额度并将周期起点对齐到当前周期
+    pub fn refresh(&mut self, now: i64) -> Result<()> {
+        let period = self.period.seconds();
+        let elapsed = now.saturating_sub(self.last_reset);
+        if elapsed >= period {
+            let advance = (elapsed / period)
+                .checked_mul(period)
+                .ok_or(MultisigError::Overflow)?;
+            self.last_reset = self
+                .last_reset
+                .checked_add(advance)
+                .ok_or(MultisigError::Overflow)?;
+            self.remaining_amount = self.amount;
+        }
+        Ok(())
+    }
+}
+
+// 定义支出限额的重置周期
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum SpendingPeriod {
+    Day,   // 每天
+    Week,  // 每周
+    Month, // 每 30 天
+}
+
+// 实现支出限额周期的辅助方法
+impl SpendingPeriod {
+    // 周期长度（秒）
+    pub fn seconds(self) -> i64 {
+        match self {
+            SpendingPeriod::Day => 24 * 60 * 60,
+            SpendingPeriod::Week => 7 * 24 * 60 * 60,
+            SpendingPeriod::Month => 30 * 24 * 60 * 60,
+        }
+    }
 }
 
 // 定义账户元数据结构
@@ -661,15 +2572,337 @@ pub struct InstructionData {
     pub data: Vec<u8>, // 指令数据
 }
 
+// 定义紧凑的交易消息结构，参照 Solana v0 消息：账户去重存放在共享账户表中，
+// 依次为可写签名账户、只读签名账户、可写非签名账户、只读非签名账户
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TransactionMessage {
+    pub num_signers: u8,              // 签名账户数量
+    pub num_writable_signers: u8,     // 可写签名账户数量
+    pub num_writable_non_signers: u8, // 可写非签名账户数量
+    #[max_len(MAX_IX_PER_TX * (MAX_ACCOUNTS_PER_IX + 1))]
+    pub account_keys: Vec<Pubkey>, // 共享账户表，包含程序 ID
+    #[max_len(MAX_IX_PER_TX, CompiledInstruction::INIT_SPACE)]
+    pub instructions: Vec<CompiledInstruction>, // 按索引引用账户表的指令列表
+    #[max_len(MAX_ADDRESS_TABLE_LOOKUPS, MessageAddressTableLookup::INIT_SPACE)]
+    pub address_table_lookups: Vec<MessageAddressTableLookup>, // 引用的地址查找表
+}
+
+// 定义紧凑消息对地址查找表的引用；超出账户表的索引依次指向各表的可写账户、只读账户
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct MessageAddressTableLookup {
+    pub account_key: Pubkey, // 地址查找表账户
+    #[max_len(MAX_IX_PER_TX * MAX_ACCOUNTS_PER_IX)]
+    pub writable_indexes: Vec<u8>, // 可写账户在查找表中的索引
+    #[max_len(MAX_IX_PER_TX * MAX_ACCOUNTS_PER_IX)]
+    pub readonly_indexes: Vec<u8>, // 只读账户在查找表中的索引
+}
+
+// 定义从地址查找表解析出的账户
+#[derive(Default)]
+pub struct LoadedAddresses {
+    pub writable: Vec<Pubkey>, // 可写账户
+    pub readonly: Vec<Pubkey>, // 只读账户
+}
+
+// 定义紧凑消息中的指令结构
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CompiledInstruction {
+    pub program_id_index: u8, // 程序 ID 在账户表中的索引
+    #[max_len(MAX_ACCOUNTS_PER_IX)]
+    pub account_indexes: Vec<u8>, // 各账户在账户表中的索引
+    #[max_len(MAX_IX_DATA_LEN)]
+    pub data: Vec<u8>, // 指令数据
+}
+
+// 实现紧凑消息的编译与还原
+impl TransactionMessage {
+    // 将指令列表编译为紧凑消息，同一账户在各指令中的签名和可写属性合并
+    pub fn compile(instructions: &[InstructionData]) -> Result<Self> {
+        // 按首次出现的顺序收集账户及其合并后的属性
+        let mut keys: Vec<AccountMetaData> = vec![];
+        let mut add = |pubkey: Pubkey, is_signer: bool, is_writable: bool| match keys
+            .iter_mut()
+            .find(|acc| acc.pubkey == pubkey)
+        {
+            Some(acc) => {
+                acc.is_signer |= is_signer;
+                acc.is_writable |= is_writable;
+            }
+            None => keys.push(AccountMetaData {
+                pubkey,
+                is_signer,
+                is_writable,
+            }),
+        };
+        for ix in instructions {
+            for acc in &ix.accounts {
+                add(acc.pubkey, acc.is_signer, acc.is_writable);
+            }
+            add(ix.program_id, false, false);
+        }
+        // 账户索引和各类账户数量以 u8 存储
+        require!(
+            keys.len() <= u8::MAX as usize,
+            MultisigError::TooManyAccounts
+        );
+        // 按签名、可写属性稳定排序
+        keys.sort_by_key(|acc| (!acc.is_signer, !acc.is_writable));
+        let count =
+            |f: fn(&AccountMetaData) -> bool| keys.iter().filter(|acc| f(acc)).count() as u8;
+        let num_signers = count(|acc| acc.is_signer);
+        let num_writable_signers = count(|acc| acc.is_signer && acc.is_writable);
+        let num_writable_non_signers = count(|acc| !acc.is_signer && acc.is_writable);
+
+        // 所有账户均已收入账户表，按公钥查找索引
+        let index_of = |pubkey: &Pubkey| {
+            keys.iter()
+                .position(|acc| acc.pubkey == *pubkey)
+                .unwrap_or_default() as u8
+        };
+        let compiled = instructions
+            .iter()
+            .map(|ix| CompiledInstruction {
+                program_id_index: index_of(&ix.program_id),
+                account_indexes: ix
+                    .accounts
+                    .iter()
+                    .map(|acc| index_of(&acc.pubkey))
+                    .collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+        Ok(Self {
+            num_signers,
+            num_writable_signers,
+            num_writable_non_signers,
+            account_keys: keys.into_iter().map(|acc| acc.pubkey).collect(),
+            instructions: compiled,
+            address_table_lookups: vec![],
+        })
+    }
+
+    // 计算序列化后的字节数
+    pub fn serialized_len(&self) -> usize {
+        let instructions_len = self
+            .instructions
+            .iter()
+            .map(|ix| 1 + 4 + ix.account_indexes.len() + 4 + ix.data.len())
+            .sum::<usize>();
+        let lookups_len = self
+            .address_table_lookups
+            .iter()
+            .map(|lookup| {
+                32 + 4 + lookup.writable_indexes.len() + 4 + lookup.readonly_indexes.len()
+            })
+            .sum::<usize>();
+        3 + 4 + 32 * self.account_keys.len() + 4 + instructions_len + 4 + lookups_len
+    }
+
+    // 计算仅含一条指令（账户互不重复）的消息序列化后的字节数
+    pub fn single_instruction_space(accounts: usize, data_len: usize) -> usize {
+        3 + 4 + 32 * (accounts + 1) + 4 + 1 + 4 + accounts + 4 + data_len + 4
+    }
+
+    // 读取账户表中指定索引的账户；程序 ID 只能位于账户表中，不能来自地址查找表
+    pub fn key(&self, index: u8) -> Result<Pubkey> {
+        self.account_keys
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| error!(MultisigError::InvalidTransactionMessage))
+    }
+
+    // 读取指定索引的账户元数据，签名和可写属性由其在账户表中的位置决定；
+    // 超出账户表的索引指向地址查找表解析出的账户，均不能签名
+    pub fn account_meta(&self, index: u8, loaded: &LoadedAddresses) -> Result<AccountMetaData> {
+        let index = index as usize;
+        let num_keys = self.account_keys.len();
+        if index >= num_keys {
+            let loaded_index = index - num_keys;
+            let (pubkey, is_writable) = match loaded.writable.get(loaded_index) {
+                Some(pubkey) => (*pubkey, true),
+                None => (
+                    *loaded
+                        .readonly
+                        .get(loaded_index - loaded.writable.len())
+                        .ok_or(MultisigError::InvalidTransactionMessage)?,
+                    false,
+                ),
+            };
+            return Ok(AccountMetaData {
+                pubkey,
+                is_signer: false,
+                is_writable,
+            });
+        }
+        let num_signers = self.num_signers as usize;
+        Ok(AccountMetaData {
+            pubkey: self.account_keys[index],
+            is_signer: index < num_signers,
+            is_writable: index < self.num_writable_signers as usize
+                || (index >= num_signers
+                    && index < num_signers + self.num_writable_non_signers as usize),
+        })
+    }
+
+    // 校验执行时提供的账户与消息账户表逐一对应：数量一致、公钥一致、可写属性一致，
+    // 且不能带有提案未声明的签名；失败时在日志中标出不匹配的账户索引
+    pub fn validate_accounts(
+        &self,
+        loaded: &LoadedAddresses,
+        accounts: &[AccountInfo],
+    ) -> Result<()> {
+        let expected = self.account_keys.len() + loaded.writable.len() + loaded.readonly.len();
+        if accounts.len() != expected {
+            msg!(
+                "Expected {} proposal accounts after the lookup tables, got {}",
+                expected,
+                accounts.len()
+            );
+            return err!(MultisigError::ExecuteAccountCountMismatch);
+        }
+        for (index, info) in accounts.iter().enumerate() {
+            self.check_account(index, loaded, info.key, info.is_signer, info.is_writable)?;
+        }
+        Ok(())
+    }
+
+    // 校验单个执行账户：公钥和可写属性需与账户表一致，签名属性不能超出账户表
+    fn check_account(
+        &self,
+        index: usize,
+        loaded: &LoadedAddresses,
+        key: &Pubkey,
+        is_signer: bool,
+        is_writable: bool,
+    ) -> Result<()> {
+        let expected = u8::try_from(index)
+            .map_err(|_| error!(MultisigError::TooManyAccounts))
+            .and_then(|i| self.account_meta(i, loaded))?;
+        // 按不符的类型返回对应错误：账户被替换、可写属性不同或签名属性被提升
+        let mismatch = if *key != expected.pubkey {
+            MultisigError::ExecuteAccountKeyMismatch
+        } else if is_writable != expected.is_writable {
+            MultisigError::ExecuteAccountWritableMismatch
+        } else if is_signer && !expected.is_signer {
+            MultisigError::ExecuteAccountSignerEscalation
+        } else {
+            return Ok(());
+        };
+        msg!(
+            "Execute account {} does not match proposal account {} (writable: {}, signer: {})",
+            index,
+            expected.pubkey,
+            expected.is_writable,
+            expected.is_signer
+        );
+        // 错误来源中记录不符账户在执行账户（查找表之后的剩余账户）中的索引
+        Err(error!(mismatch).with_account_name(format!("execute_accounts[{index}]")))
+    }
+
+    // 将紧凑消息中的一条指令还原为完整的指令数据
+    pub fn decompile(
+        &self,
+        ix: &CompiledInstruction,
+        loaded: &LoadedAddresses,
+    ) -> Result<InstructionData> {
+        Ok(InstructionData {
+            program_id: self.key(ix.program_id_index)?,
+            accounts: ix
+                .account_indexes
+                .iter()
+                .map(|&index| self.account_meta(index, loaded))
+                .collect::<Result<_>>()?,
+            data: ix.data.clone(),
+        })
+    }
+
+    // 读取各指令调用的程序 ID
+    pub fn program_ids(&self) -> Result<Vec<Pubkey>> {
+        self.instructions
+            .iter()
+            .map(|ix| self.key(ix.program_id_index))
+            .collect()
+    }
+
+    // 从地址查找表账户解析消息引用的账户，查找表需按 address_table_lookups 的顺序传入；
+    // 先依次取各表的可写账户，再依次取各表的只读账户
+    pub fn load_addresses(&self, lookup_tables: &[AccountInfo]) -> Result<LoadedAddresses> {
+        require!(
+            lookup_tables.len() == self.address_table_lookups.len(),
+            MultisigError::InvalidAddressLookupTable
+        );
+        let mut loaded = LoadedAddresses::default();
+        let mut readonly = vec![];
+        for (lookup, table) in self.address_table_lookups.iter().zip(lookup_tables) {
+            require!(
+                table.key() == lookup.account_key
+                    && *table.owner == ADDRESS_LOOKUP_TABLE_PROGRAM_ID,
+                MultisigError::InvalidAddressLookupTable
+            );
+            let data = table.try_borrow_data()?;
+            for &index in &lookup.writable_indexes {
+                loaded.writable.push(lookup_table_address(&data, index)?);
+            }
+            for &index in &lookup.readonly_indexes {
+                readonly.push(lookup_table_address(&data, index)?);
+            }
+        }
+        loaded.readonly = readonly;
+        Ok(loaded)
+    }
+
+    // 验证消息头与账户表一致，并还原全部指令
+    pub fn instructions(&self, loaded: &LoadedAddresses) -> Result<Vec<InstructionData>> {
+        let num_keys = self.account_keys.len();
+        require!(
+            self.num_writable_signers <= self.num_signers
+                && self.num_signers as usize + self.num_writable_non_signers as usize <= num_keys,
+            MultisigError::InvalidTransactionMessage
+        );
+        self.instructions
+            .iter()
+            .map(|ix| self.decompile(ix, loaded))
+            .collect()
+    }
+}
+
+// 将 Solana 指令转换为提案中存储的指令数据
+impl From<anchor_lang::solana_program::instruction::Instruction> for InstructionData {
+    fn from(ix: anchor_lang::solana_program::instruction::Instruction) -> Self {
+        Self {
+            program_id: ix.program_id,
+            accounts: ix
+                .accounts
+                .into_iter()
+                .map(|meta| AccountMetaData {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: ix.data,
+        }
+    }
+}
+
 // 定义多签创建事件
 #[event]
 pub struct MultisigCreated {
     pub multisig: Pubkey,    // 多签账户公钥
-    pub owners: Vec<Pubkey>, // owner 列表
-    pub threshold: u8,       // 批准阈值
+    pub owners: Vec<Member>, // 成员列表
+    pub threshold: u16,      // 批准阈值（投票权重之和）
     pub nonce: u64,          // 随机数
 }
 
+// 定义交易缓冲区创建事件
+#[event]
+pub struct TransactionBufferCreated {
+    pub multisig: Pubkey,           // 多签账户公钥
+    pub transaction_buffer: Pubkey, // 交易缓冲区公钥
+    pub creator: Pubkey,            // 创建者公钥
+    pub final_size: u32,            // 完整内容的长度
+}
+
 // 定义提案创建事件
 #[event]
 pub struct ProposalCreated {
@@ -677,22 +2910,41 @@ pub struct ProposalCreated {
     pub transaction: Pubkey,    // 交易账户公钥
     pub proposer: Pubkey,       // 提议者公钥
     pub instruction_count: u64, // 指令数量
+    pub vault_index: u8,        // 签名金库的索引
 }
 
 // 定义批准添加事件
 #[event]
 pub struct ApprovalAdded {
-    pub multisig: Pubkey,    // 多签账户公钥
-    pub transaction: Pubkey, // 交易账户公钥
-    pub owner: Pubkey,       // 批准者公钥
+    pub multisig: Pubkey,     // 多签账户公钥
+    pub transaction: Pubkey,  // 交易账户公钥
+    pub owner: Pubkey,        // 批准者公钥
+    pub approval_weight: u32, // 当前 owner 的有效批准权重
 }
 
 // 定义批准撤销事件
 #[event]
 pub struct ApprovalRevoked {
+    pub multisig: Pubkey,     // 多签账户公钥
+    pub transaction: Pubkey,  // 交易账户公钥
+    pub owner: Pubkey,        // 撤销者公钥
+    pub approval_weight: u32, // 当前 owner 的有效批准权重
+}
+
+// 定义拒绝添加事件
+#[event]
+pub struct RejectionAdded {
+    pub multisig: Pubkey,      // 多签账户公钥
+    pub transaction: Pubkey,   // 交易账户公钥
+    pub owner: Pubkey,         // 拒绝者公钥
+    pub rejection_weight: u32, // 当前 owner 的有效拒绝权重
+}
+
+// 定义提案被拒绝事件
+#[event]
+pub struct ProposalRejected {
     pub multisig: Pubkey,    // 多签账户公钥
     pub transaction: Pubkey, // 交易账户公钥
-    pub owner: Pubkey,       // 撤销者公钥
 }
 
 // 定义提案取消事件
@@ -703,11 +2955,30 @@ pub struct ProposalCancelled {
     pub canceller: Pubkey,   // 取消者公钥
 }
 
+// 定义提案刷新事件
+#[event]
+pub struct ProposalRefreshed {
+    pub multisig: Pubkey,       // 多签账户公钥
+    pub transaction: Pubkey,    // 交易账户公钥
+    pub config_generation: u32, // 刷新后的配置代数
+}
+
 // 定义交易执行事件
 #[event]
 pub struct TransactionExecuted {
     pub multisig: Pubkey,    // 多签账户公钥
     pub transaction: Pubkey, // 交易账户公钥
+    pub executor: Pubkey,    // 执行者公钥
+}
+
+// 定义分步执行事件，每执行一条指令触发一次
+#[event]
+pub struct TransactionStepExecuted {
+    pub multisig: Pubkey,    // 多签账户公钥
+    pub transaction: Pubkey, // 交易账户公钥
+    pub executor: Pubkey,    // 执行者公钥
+    pub ix_index: u16,       // 本次执行的指令索引
+    pub remaining: u16,      // 剩余待执行的指令数量
 }
 
 // 定义交易关闭事件
@@ -715,14 +2986,22 @@ pub struct TransactionExecuted {
 pub struct TransactionClosed {
     pub multisig: Pubkey,    // 多签账户公钥
     pub transaction: Pubkey, // 交易账户公钥
-    pub recipient: Pubkey,   // 租金接收者公钥
+    pub recipient: Pubkey,   // 租金接收者（提议者）公钥
+    pub closer: Pubkey,      // 关闭者公钥
 }
 
 // 定义阈值变更事件
 #[event]
 pub struct ThresholdChanged {
-    pub multisig: Pubkey,  // 多签账户公钥
-    pub new_threshold: u8, // 新阈值
+    pub multisig: Pubkey,   // 多签账户公钥
+    pub new_threshold: u16, // 新阈值
+}
+
+// 定义配置变更阈值变更事件
+#[event]
+pub struct ConfigThresholdChanged {
+    pub multisig: Pubkey,          // 多签账户公钥
+    pub new_config_threshold: u16, // 新的配置变更阈值
 }
 
 // 定义 owner 添加事件
@@ -730,6 +3009,16 @@ pub struct ThresholdChanged {
 pub struct OwnerAdded {
     pub multisig: Pubkey,  // 多签账户公钥
     pub new_owner: Pubkey, // 新 owner 公钥
+    pub permissions: u8,   // 新 owner 的权限位
+    pub weight: u16,       // 新 owner 的投票权重
+}
+
+// 定义 owner 权重变更事件
+#[event]
+pub struct OwnerWeightChanged {
+    pub multisig: Pubkey, // 多签账户公钥
+    pub owner: Pubkey,    // owner 公钥
+    pub weight: u16,      // 新的投票权重
 }
 
 // 定义 owner 移除事件
@@ -746,11 +3035,132 @@ pub struct PauseToggled {
     pub paused: bool,     // 暂停状态
 }
 
-// 定义白名单程序添加事件
+// 定义时间锁变更事件
+#[event]
+pub struct TimeLockChanged {
+    pub multisig: Pubkey,       // 多签账户公钥
+    pub time_lock_seconds: u32, // 新的时间锁延迟（秒）
+}
+
+// 定义执行者策略变更事件
+#[event]
+pub struct ExecutorPolicyChanged {
+    pub multisig: Pubkey,       // 多签账户公钥
+    pub policy: ExecutorPolicy, // 新的执行者策略
+    pub executors: Vec<Pubkey>, // 指定执行者列表
+}
+
+// 定义 guard 程序变更事件
+#[event]
+pub struct GuardProgramChanged {
+    pub multisig: Pubkey,              // 多签账户公钥
+    pub guard_program: Option<Pubkey>, // 新的 guard 程序，None 表示已清除
+}
+
+// 定义白名单程序添加事件
+#[event]
+pub struct WhitelistProgramAdded {
+    pub multisig: Pubkey,        // 多签账户公钥
+    pub program_id: Pubkey,      // 添加的程序 ID
+    pub required_approvals: u16, // 风险等级阈值
+}
+
+// 定义白名单程序风险等级变更事件
+#[event]
+pub struct WhitelistTierUpdated {
+    pub multisig: Pubkey,        // 多签账户公钥
+    pub program_id: Pubkey,      // 程序 ID
+    pub required_approvals: u16, // 新的风险等级阈值
+}
+
+// 定义支出限额添加事件
+#[event]
+pub struct SpendingLimitAdded {
+    pub multisig: Pubkey,       // 多签账户公钥
+    pub spending_limit: Pubkey, // 支出限额账户公钥
+    pub mint: Pubkey,           // 代币 mint
+    pub amount: u64,            // 每个周期的额度
+    pub period: SpendingPeriod, // 额度重置周期
+}
+
+// 定义支出限额移除事件
+#[event]
+pub struct SpendingLimitRemoved {
+    pub multisig: Pubkey,       // 多签账户公钥
+    pub spending_limit: Pubkey, // 支出限额账户公钥
+}
+
+// 定义支出限额使用事件
+#[event]
+pub struct SpendingLimitUsed {
+    pub multisig: Pubkey,       // 多签账户公钥
+    pub spending_limit: Pubkey, // 支出限额账户公钥
+    pub member: Pubkey,         // 使用额度的成员
+    pub destination: Pubkey,    // 目标地址
+    pub amount: u64,            // 转出金额
+    pub remaining_amount: u64,  // 当前周期剩余额度
+}
+
+// 定义金库代币账户创建事件
+#[event]
+pub struct VaultTokenAccountCreated {
+    pub multisig: Pubkey,      // 多签账户公钥
+    pub mint: Pubkey,          // 代币 mint
+    pub token_account: Pubkey, // 创建的代币账户
+}
+
+// 定义金库代币转账事件
+#[event]
+pub struct VaultTokenTransferred {
+    pub multisig: Pubkey,    // 多签账户公钥
+    pub mint: Pubkey,        // 代币 mint
+    pub destination: Pubkey, // 目标代币账户
+    pub amount: u64,         // 转出数量（含手续费）
+    pub fee: u64,            // Token-2022 转账手续费
+}
+
+// 定义金库代币账户关闭事件
+#[event]
+pub struct VaultTokenAccountClosed {
+    pub multisig: Pubkey,      // 多签账户公钥
+    pub token_account: Pubkey, // 关闭的代币账户
+}
+
+// 定义地址簿启用状态变更事件
+#[event]
+pub struct AddressBookToggled {
+    pub multisig: Pubkey, // 多签账户公钥
+    pub enabled: bool,    // 是否启用
+}
+
+// 定义地址簿地址添加事件
+#[event]
+pub struct AddressBookEntryAdded {
+    pub multisig: Pubkey, // 多签账户公钥
+    pub address: Pubkey,  // 添加的地址
+}
+
+// 定义地址簿地址移除事件
+#[event]
+pub struct AddressBookEntryRemoved {
+    pub multisig: Pubkey, // 多签账户公钥
+    pub address: Pubkey,  // 移除的地址
+}
+
+// 定义允许的指令前缀添加事件
+#[event]
+pub struct AllowedPrefixAdded {
+    pub multisig: Pubkey,   // 多签账户公钥
+    pub program_id: Pubkey, // 程序 ID
+    pub prefix: Vec<u8>,    // 添加的指令前缀
+}
+
+// 定义允许的指令前缀移除事件
 #[event]
-pub struct WhitelistProgramAdded {
+pub struct AllowedPrefixRemoved {
     pub multisig: Pubkey,   // 多签账户公钥
-    pub program_id: Pubkey, // 添加的程序 ID
+    pub program_id: Pubkey, // 程序 ID
+    pub prefix: Vec<u8>,    // 移除的指令前缀
 }
 
 // 定义白名单程序移除事件
@@ -760,31 +3170,354 @@ pub struct WhitelistProgramRemoved {
     pub program_id: Pubkey, // 移除的程序 ID
 }
 
+// 提案的参数
+struct ProposalArgs {
+    message: TransactionMessage,       // 编译后的紧凑消息
+    loaded_addresses: LoadedAddresses, // 从地址查找表解析出的账户
+    expires_at: Option<i64>,           // 交易过期时间（可选）
+    auto_approve: bool,                // 是否自动批准
+    vault_index: u8,                   // 签名金库的索引
+    ephemeral_signers: u8,             // 声明的临时签名者数量
+    from_buffer: bool,                 // 是否来自交易缓冲区，缓冲区提案不受单笔提案的数量限制
+    execution_mode: ExecutionMode,     // 执行方式
+    assertions: Vec<BalanceAssertion>, // 执行后需成立的余额断言
+}
+
+// 校验提案并初始化交易账户，供通用提案、转账快捷提案和交易缓冲区提案共用
+fn create_proposal(
+    ms: &mut Account<Multisig>,
+    whitelist: &Account<ProgramWhitelist>,
+    address_book: &Account<AddressBook>,
+    vault_key: Pubkey,
+    tx: &mut Account<Transaction>,
+    proposer_key: Pubkey,
+    args: ProposalArgs,
+) -> Result<()> {
+    let ProposalArgs {
+        message,
+        loaded_addresses,
+        expires_at,
+        auto_approve,
+        vault_index,
+        ephemeral_signers,
+        from_buffer,
+        execution_mode,
+        assertions,
+    } = args;
+    // 还原消息中的指令，校验按实际执行时的账户属性进行
+    require!(
+        message.address_table_lookups.len() <= MAX_ADDRESS_TABLE_LOOKUPS,
+        MultisigError::InvalidAddressLookupTable
+    );
+    let instructions = message.instructions(&loaded_addresses)?;
+    // 验证传入的金库账户与所选索引的金库地址一致
+    let (expected_vault, vault_bump) = ms.vault_address(&ms.key(), vault_index)?;
+    require!(vault_key == expected_vault, MultisigError::InvalidVault);
+    // 确保多签账户未暂停
+    // require!(!ms.paused, MultisigError::Paused);
+    let is_resume_proposal = instructions.len() == 1
+        && is_pause_instruction(
+            &instructions[0].program_id,
+            &instructions[0].data,
+            &crate::ID,
+            false,
+        );
+    if !is_resume_proposal && ms.paused {
+        return err!(MultisigError::Paused);
+    }
+    // 验证指令列表不为空且不超过最大限制，缓冲区提案的大小已由缓冲区长度限制
+    require!(
+        !instructions.is_empty() && (from_buffer || instructions.len() <= MAX_IX_PER_TX),
+        MultisigError::TooManyInstructions
+    );
+
+    // 确保提议者是拥有提案权限的成员
+    require!(ms.is_member(&proposer_key), MultisigError::NotAnOwner);
+    require!(
+        ms.has_permission(&proposer_key, PERMISSION_PROPOSE),
+        MultisigError::MissingPermission
+    );
+
+    // 验证过期时间（若设置）晚于当前时间
+    let now = Clock::get()?.unix_timestamp;
+    if let Some(exp) = expires_at {
+        require!(exp > now, MultisigError::InvalidExpiration);
+    }
+
+    // 推导本交易声明的临时签名者，用于在提案中创建需要自签名的新账户
+    require!(
+        ephemeral_signers as usize <= MAX_EPHEMERAL_SIGNERS,
+        MultisigError::TooManyEphemeralSigners
+    );
+    let tx_key = tx.key();
+    let (ephemeral_keys, ephemeral_bumps): (Vec<Pubkey>, Vec<u8>) = (0..ephemeral_signers)
+        .map(|index| ephemeral_signer_address(&tx_key, index))
+        .unzip();
+
+    // 按当前白名单和地址簿验证指令，并验证指令的账户和数据长度
+    validate_instructions(
+        &ms.key(),
+        whitelist,
+        address_book,
+        vault_key,
+        &ephemeral_keys,
+        &instructions,
+    )?;
+    if !from_buffer {
+        instructions.iter().try_for_each(validate_ix_bounds)?;
+    }
+
+    // 验证余额断言：数量受限，只能用于原子执行的提案，且断言的账户需出现在提案指令中
+    require!(
+        assertions.len() <= MAX_BALANCE_ASSERTIONS,
+        MultisigError::TooManyBalanceAssertions
+    );
+    require!(
+        assertions.is_empty() || execution_mode == ExecutionMode::Atomic,
+        MultisigError::InvalidBalanceAssertion
+    );
+    for assertion in &assertions {
+        require!(
+            instructions
+                .iter()
+                .any(|ix| ix.accounts.iter().any(|a| a.pubkey == *assertion.account())),
+            MultisigError::InvalidBalanceAssertion
+        );
+    }
+
+    // 初始化交易账户
+    tx.multisig = ms.key(); // 设置关联的多签账户
+    tx.id = ms.next_tx_id; // 设置交易 ID
+    tx.proposer = proposer_key; // 设置提议者
+    tx.vault_index = vault_index; // 记录签名金库的索引
+    tx.vault_bump = vault_bump; // 记录签名金库的 bump seed
+    tx.ephemeral_signer_bumps = ephemeral_bumps; // 记录临时签名者的 bump seed
+    tx.message = message; // 设置紧凑消息
+    tx.execution_mode = execution_mode; // 设置执行方式
+    tx.next_ix_index = 0; // 从第一条指令开始执行
+    tx.assertions = assertions; // 设置余额断言
+    tx.approvals = vec![]; // 初始化批准列表
+    tx.rejections = vec![]; // 初始化拒绝列表
+    tx.expires_at = expires_at; // 设置过期时间
+    tx.config_generation = ms.config_generation; // 记录提案时的配置代数
+    tx.status = ProposalStatus::Draft; // 初始状态为草稿
+    tx.timestamps = ProposalTimestamps {
+        created_at: now,
+        ..Default::default()
+    };
+    // 指令校验通过，提案进入投票阶段
+    tx.transition_to(ProposalStatus::Active, now)?;
+
+    // 如果启用自动批准，调用批准逻辑
+    if auto_approve {
+        approve_impl(ms, whitelist, tx, &proposer_key)?;
+    }
+
+    // 更新下一个交易 ID，防止溢出
+    ms.next_tx_id = ms
+        .next_tx_id
+        .checked_add(1)
+        .ok_or(MultisigError::Overflow)?;
+
+    // 触发提案创建事件
+    emit!(ProposalCreated {
+        multisig: ms.key(),
+        transaction: tx.key(),
+        proposer: proposer_key,
+        instruction_count: tx.message.instructions.len() as u64,
+        vault_index,
+    });
+
+    // 返回成功
+    Ok(())
+}
+
+// 计算 Token-2022 转账手续费，mint 未配置手续费扩展时返回 None
+fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<Option<u64>> {
+    let Ok(config) = get_mint_extension_data::<TransferFeeConfig>(mint) else {
+        return Ok(None);
+    };
+    let fee = config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(MultisigError::Overflow)?;
+    Ok(Some(fee))
+}
+
+// 验证转账收款地址有效且不是金库自身
+fn validate_transfer_destination(to: &Pubkey, vault: &Pubkey) -> Result<()> {
+    require!(
+        *to != Pubkey::default() && to != vault,
+        MultisigError::InvalidDestination
+    );
+    Ok(())
+}
+
 // 批准交易的辅助函数
 fn approve_impl(
     ms: &Account<Multisig>,
+    whitelist: &ProgramWhitelist,
     tx: &mut Account<Transaction>,
     owner: &Pubkey,
 ) -> Result<()> {
     // 确保批准者是 owner
-    require!(ms.owners.contains(owner), MultisigError::NotAnOwner);
+    require!(ms.is_member(owner), MultisigError::NotAnOwner);
+    // 确保批准者拥有投票权限
+    require!(
+        ms.has_permission(owner, PERMISSION_VOTE),
+        MultisigError::MissingPermission
+    );
     // 确保未重复批准
     require!(
         !tx.approvals.contains(owner),
         MultisigError::AlreadyApproved
     );
+    // 批准视为改票，移除该 owner 之前的拒绝
+    tx.rejections.retain(|k| k != owner);
     // 添加批准者到批准列表
     tx.approvals.push(*owner);
+    // 批准数达到阈值时提案进入已批准状态，作为时间锁起点
+    sync_approval_status(ms, whitelist, tx, Clock::get()?.unix_timestamp)?;
     // 返回成功
     Ok(())
 }
 
+// 根据当前有效批准数，在投票中与已批准状态之间同步提案状态
+fn sync_approval_status(
+    ms: &Multisig,
+    whitelist: &ProgramWhitelist,
+    tx: &mut Transaction,
+    now: i64,
+) -> Result<()> {
+    let threshold = ms.threshold_for(whitelist, &tx.message.program_ids()?);
+    let reached = ms.meets_threshold(&tx.approvals, threshold);
+    match tx.status {
+        ProposalStatus::Active if reached => tx.transition_to(ProposalStatus::Approved, now),
+        ProposalStatus::Approved if !reached => tx.transition_to(ProposalStatus::Active, now),
+        _ => Ok(()),
+    }
+}
+
+// 统计成员列表中拥有投票权限的成员权重之和
+fn total_vote_weight(owners: &[Member]) -> u32 {
+    owners
+        .iter()
+        .filter(|m| m.has_permission(PERMISSION_VOTE))
+        .map(|m| m.weight as u32)
+        .sum()
+}
+
+// 验证投票权重大于 0
+fn validate_weight(weight: u16) -> Result<()> {
+    require!(weight > 0, MultisigError::InvalidWeight);
+    Ok(())
+}
+
+// 验证权限位非空且只包含已定义的权限
+fn validate_permissions(permissions: u8) -> Result<()> {
+    require!(
+        permissions != 0 && permissions & !PERMISSION_ALL == 0,
+        MultisigError::InvalidPermissions
+    );
+    Ok(())
+}
+
 // 检查指令中的签名者是否为金库账户
-fn is_signer_allowed(ix: &InstructionData, vault_key: &Pubkey) -> bool {
+fn is_signer_allowed(ix: &InstructionData, signers: &[Pubkey]) -> bool {
     ix.accounts
         .iter()
         .filter(|acc| acc.is_signer)
-        .all(|acc| acc.pubkey == *vault_key)
+        .all(|acc| signers.contains(&acc.pubkey))
+}
+
+// 读取地址查找表账户数据中指定索引的地址
+fn lookup_table_address(data: &[u8], index: u8) -> Result<Pubkey> {
+    let start = LOOKUP_TABLE_META_SIZE + index as usize * 32;
+    data.get(start..start + 32)
+        .and_then(|key| Pubkey::try_from(key).ok())
+        .ok_or_else(|| error!(MultisigError::InvalidAddressLookupTable))
+}
+
+// 推导支出限额管理指令写入的支出限额 PDA：[b"spending_limit", multisig, mint]，
+// 两条指令均以 mint 作为第一个参数
+fn spending_limit_address(ix: &InstructionData, multisig: &Pubkey) -> Option<Pubkey> {
+    // 仅处理本程序的添加/移除支出限额指令
+    let is_limit_ix = ix
+        .data
+        .starts_with(crate::instruction::AddSpendingLimit::DISCRIMINATOR)
+        || ix
+            .data
+            .starts_with(crate::instruction::RemoveSpendingLimit::DISCRIMINATOR);
+    if ix.program_id != crate::ID || !is_limit_ix {
+        return None;
+    }
+    // 从指令数据中读取 mint 参数
+    let mint = Pubkey::try_from(ix.data.get(8..40)?).ok()?;
+    Some(
+        Pubkey::find_program_address(
+            &[b"spending_limit", multisig.as_ref(), mint.as_ref()],
+            &crate::ID,
+        )
+        .0,
+    )
+}
+
+// 读取金库代币指令写入的账户：金库代币账户由上下文约束归金库所有，
+// 转账的目标代币账户由 vault_token_transfer 在执行时按地址簿校验其所有者
+fn vault_token_accounts(ix: &InstructionData) -> Vec<Pubkey> {
+    // 仅处理本程序的指令
+    if ix.program_id != crate::ID {
+        return vec![];
+    }
+    // 上述账户在各指令上下文中的位置
+    let positions: [(&[u8], &[usize]); 3] = [
+        (
+            crate::instruction::CreateVaultTokenAccount::DISCRIMINATOR,
+            &[3],
+        ),
+        (
+            crate::instruction::VaultTokenTransfer::DISCRIMINATOR,
+            &[4, 5],
+        ),
+        (
+            crate::instruction::CloseVaultTokenAccount::DISCRIMINATOR,
+            &[2],
+        ),
+    ];
+    positions
+        .iter()
+        .find(|(discriminator, _)| ix.data.starts_with(discriminator))
+        .map(|(_, indexes)| {
+            indexes
+                .iter()
+                .filter_map(|&index| ix.accounts.get(index).map(|acc| acc.pubkey))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 读取由金库签名、从金库关联代币账户转出的 transfer_checked 指令的源账户
+fn vault_token_source(ix: &InstructionData, vault: &Pubkey) -> Option<Pubkey> {
+    // 仅处理 Token 或 Token-2022 程序的 transfer_checked 指令
+    let is_token_program = ix.program_id == token::ID || ix.program_id == token_2022::ID;
+    let is_transfer_checked = matches!(
+        TokenInstruction::unpack(&ix.data),
+        Ok(TokenInstruction::TransferChecked { .. })
+    );
+    if !is_token_program || !is_transfer_checked {
+        return None;
+    }
+    // 账户顺序为源账户、mint、目标账户、授权者；源账户需是金库的关联代币账户
+    let [source, mint, _, authority] = ix.accounts.get(..4)? else {
+        return None;
+    };
+    let vault_ata =
+        get_associated_token_address_with_program_id(vault, &mint.pubkey, &ix.program_id);
+    (authority.pubkey == *vault && source.pubkey == vault_ata).then_some(vault_ata)
+}
+
+// 推导交易的临时签名者地址及 bump：[b"ephemeral", transaction, index]
+fn ephemeral_signer_address(transaction: &Pubkey, index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"ephemeral", transaction.as_ref(), &[index]], &crate::ID)
 }
 
 // 验证指令的账户和数据长度
@@ -803,19 +3536,330 @@ fn validate_ix_bounds(ix: &InstructionData) -> Result<()> {
     Ok(())
 }
 
+// 按当前白名单和地址簿验证提案的指令，提出提案和刷新提案时使用同一套规则
+fn validate_instructions(
+    multisig: &Pubkey,
+    whitelist: &Account<ProgramWhitelist>,
+    address_book: &Account<AddressBook>,
+    vault_key: Pubkey,
+    ephemeral_keys: &[Pubkey],
+    instructions: &[InstructionData],
+) -> Result<()> {
+    // 允许作为 signer 的账户：所选金库和临时签名者
+    let mut signers = vec![vault_key];
+    signers.extend_from_slice(ephemeral_keys);
+
+    // 多签自身的账户及临时签名者可以被标记为可写，不受地址簿限制
+    let mut own_accounts = vec![*multisig, vault_key, whitelist.key(), address_book.key()];
+    own_accounts.extend_from_slice(ephemeral_keys);
+
+    // 验证指令的程序 ID 是否在白名单中
+    for ix in instructions {
+        require!(
+            whitelist.contains(&ix.program_id),
+            MultisigError::ProgramNotAllowed
+        );
+        // 验证指令数据匹配该程序允许的指令前缀
+        require!(
+            whitelist.allows_data(&ix.program_id, &ix.data),
+            MultisigError::InstructionNotAllowed
+        );
+        // 启用地址簿时，验证可写账户均为地址簿中的地址；
+        // 支出限额管理指令写入的支出限额 PDA 同样属于多签自身，
+        // 金库代币指令写入的账户由其上下文约束或在执行时按地址簿校验，均只对该指令本身豁免
+        let mut ix_own_accounts = own_accounts.clone();
+        ix_own_accounts.extend(spending_limit_address(ix, multisig));
+        ix_own_accounts.extend(vault_token_accounts(ix));
+        // 从所选金库关联代币账户转出的代币转账，源账户同样属于多签自身
+        ix_own_accounts.extend(vault_token_source(ix, &vault_key));
+        require!(
+            address_book.allows_writable(ix, &ix_own_accounts),
+            MultisigError::DestinationNotAllowed
+        );
+        // 验证指令中的 signer 是所选金库账户或临时签名者
+        require!(
+            is_signer_allowed(ix, &signers),
+            MultisigError::SignerNotAllowed
+        );
+    }
+    Ok(())
+}
+
+// 检查分步执行中的提案能否继续下一步：多签未暂停，且开始执行后配置未被其他提案变更
+fn require_continuable(ms: &Multisig, tx: &Transaction) -> Result<()> {
+    // 确保多签账户未暂停
+    require!(!ms.paused, MultisigError::Paused);
+    // 确保执行期间 owner 集合或阈值未发生变更，例如轮换被盗用的密钥后剩余步骤不再执行
+    require!(
+        tx.config_generation == ms.config_generation,
+        MultisigError::StaleProposal
+    );
+    Ok(())
+}
+
+// 检查提案满足执行条件：多签未暂停（恢复提案除外）、已获批准、配置未变更、未过期、
+// 当前 owner 的批准达到阈值且时间锁已结束
+fn require_executable(
+    ms: &Multisig,
+    whitelist: &ProgramWhitelist,
+    tx: &Transaction,
+    now: i64,
+) -> Result<()> {
+    // 确保多签账户未暂停
+    if !tx.is_resume_proposal()? && ms.paused {
+        return err!(MultisigError::Paused);
+    }
+    // 确保提案已获批准
+    tx.require_approved()?;
+    // 确保提案之后 owner 集合或阈值未发生变更，旧配置下的批准不再有效
+    require!(
+        tx.config_generation == ms.config_generation,
+        MultisigError::StaleProposal
+    );
+    // 验证交易未过期
+    if let Some(exp) = tx.expires_at {
+        require!(now <= exp, MultisigError::Expired);
+    }
+    // 确保当前 owner 的有效批准权重达到阈值，已移除 owner 的批准不计入；
+    // 包含对本程序调用的提案需达到配置变更阈值，并满足所调用程序的风险等级阈值
+    let threshold = ms.threshold_for(whitelist, &tx.message.program_ids()?);
+    require!(
+        ms.meets_threshold(&tx.approvals, threshold),
+        MultisigError::NotEnoughApprovals
+    );
+    // 若启用时间锁，确保达到阈值后已经过了冷却期
+    if ms.time_lock_seconds > 0 {
+        let approved_at = tx
+            .timestamps
+            .approved_at
+            .ok_or(MultisigError::TimeLockNotElapsed)?;
+        let unlocks_at = approved_at
+            .checked_add(ms.time_lock_seconds as i64)
+            .ok_or(MultisigError::Overflow)?;
+        require!(now >= unlocks_at, MultisigError::TimeLockNotElapsed);
+    }
+    Ok(())
+}
+
+// 以提案所选金库及临时签名者的身份执行紧凑消息中指定范围的指令；
+// 剩余账户中最前面的是消息引用的地址查找表，之后的账户需按消息账户表的顺序传入；
+// 设置了 guard 程序时，最后依次传入 guard 程序及其钩子所需的账户
+fn execute_instructions<'info>(
+    ms: &Account<'info, Multisig>,
+    tx: &Account<'info, Transaction>,
+    remaining_accounts: &[AccountInfo<'info>],
+    range: Range<usize>,
+) -> Result<()> {
+    let multisig_key = &ms.key();
+    // 设置提案所选金库的 PDA seed，索引 0 沿用旧版金库的 seed
+    let index_seed = [tx.vault_index];
+    let bump_seed = [tx.vault_bump];
+    let seeds: &[&[u8]] = if tx.vault_index == 0 {
+        &[b"vault", multisig_key.as_ref(), &bump_seed]
+    } else {
+        &[b"vault", multisig_key.as_ref(), &index_seed, &bump_seed]
+    };
+    // 设置临时签名者的 PDA seed，与金库一同签名
+    let tx_key = tx.key();
+    let ephemeral_bytes: Vec<[[u8; 1]; 2]> = tx
+        .ephemeral_signer_bumps
+        .iter()
+        .enumerate()
+        .map(|(index, bump)| [[index as u8], [*bump]])
+        .collect();
+    let ephemeral_seeds: Vec<[&[u8]; 4]> = ephemeral_bytes
+        .iter()
+        .map(|[index, bump]| [b"ephemeral".as_ref(), tx_key.as_ref(), index, bump])
+        .collect();
+    let mut signer_seeds: Vec<&[&[u8]]> = vec![seeds];
+    signer_seeds.extend(ephemeral_seeds.iter().map(|s| s.as_slice()));
+
+    // 从地址查找表解析账户
+    let message = &tx.message;
+    let num_lookups = message
+        .address_table_lookups
+        .len()
+        .min(remaining_accounts.len());
+    let (lookup_tables, accounts) = remaining_accounts.split_at(num_lookups);
+    let loaded = message.load_addresses(lookup_tables)?;
+    // 设置 guard 程序时，消息账户之后的账户交给 guard 钩子；设置 guard 的提案不经过钩子
+    let guard_program = match ms.guard_program {
+        Some(guard_program) if !tx.is_guard_update_proposal()? => Some(guard_program),
+        _ => None,
+    };
+    let num_message_accounts =
+        message.account_keys.len() + loaded.writable.len() + loaded.readonly.len();
+    let (cpi_accounts, guard_accounts) = match guard_program {
+        Some(_) => accounts.split_at(num_message_accounts.min(accounts.len())),
+        None => (accounts, &[][..]),
+    };
+    // 消息账户需按账户表的顺序逐一对应，防止替换账户或提升权限
+    message.validate_accounts(&loaded, cpi_accounts)?;
+
+    // 执行指定范围的指令，从紧凑消息的账户表重建指令
+    let instructions = message
+        .instructions
+        .get(range)
+        .ok_or(MultisigError::InvalidTransactionMessage)?;
+    // 执行前调用 guard 程序的钩子，传入本次执行的指令列表
+    let guard_instructions = match guard_program {
+        Some(_) => instructions
+            .iter()
+            .map(|ix| message.decompile(ix, &loaded))
+            .collect::<Result<Vec<_>>>()?,
+        None => vec![],
+    };
+    if let Some(guard_program) = guard_program {
+        invoke_guard(
+            &guard_program,
+            GUARD_PRE_EXECUTE,
+            ms,
+            tx,
+            guard_accounts,
+            &guard_instructions,
+        )?;
+    }
+    for ix in instructions {
+        let instruction = anchor_lang::solana_program::instruction::Instruction {
+            program_id: message.key(ix.program_id_index)?,
+            accounts: to_account_metas(message, &loaded, &ix.account_indexes)?, // 按索引转换账户元数据
+            data: ix.data.clone(),
+        };
+        // 每条指令只传入其程序和引用的账户，其余账户不进入 CPI 上下文
+        let account_infos = std::iter::once(&ix.program_id_index)
+            .chain(&ix.account_indexes)
+            .map(|&index| cpi_accounts[index as usize].clone())
+            .collect::<Vec<_>>();
+        // 使用金库及临时签名者签名调用指令
+        invoke_signed(&instruction, &account_infos, &signer_seeds)?;
+    }
+    // 执行后调用 guard 程序的钩子
+    if let Some(guard_program) = guard_program {
+        invoke_guard(
+            &guard_program,
+            GUARD_POST_EXECUTE,
+            ms,
+            tx,
+            guard_accounts,
+            &guard_instructions,
+        )?;
+    }
+    Ok(())
+}
+
+// 构造 guard 钩子的指令数据：按 Anchor 规则由钩子名计算的 8 字节 discriminator，之后是指令列表
+fn guard_hook_data(hook: &str, instructions: &[InstructionData]) -> Result<Vec<u8>> {
+    let mut data = hash(format!("global:{hook}").as_bytes()).to_bytes()[..8].to_vec();
+    instructions.serialize(&mut data)?;
+    Ok(data)
+}
+
+// 调用 guard 程序的钩子，依次传入多签账户、交易账户（均为只读）和 guard 所需的其他账户；
+// 不使用金库签名，guard 报错时整笔执行回滚
+fn invoke_guard<'info>(
+    guard_program: &Pubkey,
+    hook: &str,
+    ms: &Account<'info, Multisig>,
+    tx: &Account<'info, Transaction>,
+    guard_accounts: &[AccountInfo<'info>],
+    instructions: &[InstructionData],
+) -> Result<()> {
+    // 第一个 guard 账户需为多签设置的 guard 程序
+    let (program, accounts) = guard_accounts
+        .split_first()
+        .ok_or(MultisigError::InvalidGuardProgram)?;
+    require_keys_eq!(
+        *program.key,
+        *guard_program,
+        MultisigError::InvalidGuardProgram
+    );
+    let mut metas = vec![
+        anchor_lang::solana_program::instruction::AccountMeta::new_readonly(ms.key(), false),
+        anchor_lang::solana_program::instruction::AccountMeta::new_readonly(tx.key(), false),
+    ];
+    metas.extend(accounts.iter().map(|info| {
+        anchor_lang::solana_program::instruction::AccountMeta {
+            pubkey: *info.key,
+            is_signer: info.is_signer,
+            is_writable: info.is_writable,
+        }
+    }));
+    let instruction = anchor_lang::solana_program::instruction::Instruction {
+        program_id: *guard_program,
+        accounts: metas,
+        data: guard_hook_data(hook, instructions)?,
+    };
+    let mut account_infos = vec![program.clone(), ms.to_account_info(), tx.to_account_info()];
+    account_infos.extend_from_slice(accounts);
+    invoke(&instruction, &account_infos)?;
+    Ok(())
+}
+
+// 在剩余账户中查找余额断言检查的账户
+fn assertion_account<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    assertion: &BalanceAssertion,
+) -> Result<&'a AccountInfo<'info>> {
+    accounts
+        .iter()
+        .find(|info| info.key == assertion.account())
+        .ok_or_else(|| error!(MultisigError::InvalidBalanceAssertion))
+}
+
+// 读取各余额断言执行前的基准余额；代币余额只在执行后读取，账户可能由提案创建
+fn balance_baselines(
+    assertions: &[BalanceAssertion],
+    accounts: &[AccountInfo],
+) -> Result<Vec<u64>> {
+    assertions
+        .iter()
+        .map(|assertion| match assertion {
+            BalanceAssertion::MaxLamportDecrease { .. } => {
+                assertion.balance(assertion_account(accounts, assertion)?)
+            }
+            BalanceAssertion::MinTokenBalance { .. } => Ok(0),
+        })
+        .collect()
+}
+
+// 执行后检查各余额断言，失败时在日志中标出不成立的断言
+fn check_balance_assertions(
+    assertions: &[BalanceAssertion],
+    accounts: &[AccountInfo],
+    baselines: &[u64],
+) -> Result<()> {
+    for (index, (assertion, &before)) in assertions.iter().zip(baselines).enumerate() {
+        let after = assertion.balance(assertion_account(accounts, assertion)?)?;
+        if !assertion.holds(before, after) {
+            msg!(
+                "Balance assertion {} failed for {}: before {}, after {}",
+                index,
+                assertion.account(),
+                before,
+                after
+            );
+            return err!(MultisigError::BalanceAssertionFailed);
+        }
+    }
+    Ok(())
+}
+
 // 将 AccountMetaData 转换为 Solana 的 AccountMeta
 fn to_account_metas(
-    accounts: &[AccountMetaData],
-) -> Vec<anchor_lang::solana_program::instruction::AccountMeta> {
-    accounts
+    message: &TransactionMessage,
+    loaded: &LoadedAddresses,
+    account_indexes: &[u8],
+) -> Result<Vec<anchor_lang::solana_program::instruction::AccountMeta>> {
+    account_indexes
         .iter()
-        .map(
-            |acc| anchor_lang::solana_program::instruction::AccountMeta {
+        .map(|&index| {
+            let acc = message.account_meta(index, loaded)?;
+            Ok(anchor_lang::solana_program::instruction::AccountMeta {
                 pubkey: acc.pubkey,
                 is_signer: acc.is_signer,
                 is_writable: acc.is_writable,
-            },
-        )
+            })
+        })
         .collect()
 }
 
@@ -853,27 +3897,823 @@ pub enum MultisigError {
     CannotCancelApprovedProposal, // 无法取消已批准的提案
     #[msg("Only an owner or the original proposer can close this transaction.")]
     ClosePermissionDenied, // 关闭权限被拒绝
+    #[msg("The owner set or threshold changed after this proposal was created.")]
+    StaleProposal, // 提案已过时
+    #[msg("The proposal is not stale and does not need to be refreshed.")]
+    ProposalNotStale, // 提案未过时
+    #[msg("The time lock delay has not elapsed since the proposal reached its threshold.")]
+    TimeLockNotElapsed, // 时间锁未到期
+    InvalidTimeLock,              // 时间锁延迟无效
+    AlreadyRejected,              // 已拒绝
+    #[msg("The proposal has been rejected and can no longer be approved or executed.")]
+    ProposalRejected, // 提案已被拒绝
+    #[msg("The proposal is not open for voting.")]
+    ProposalNotActive, // 提案不在投票阶段
+    InvalidStatusTransition,      // 提案状态迁移无效
+    #[msg("The member does not hold the permission required for this action.")]
+    MissingPermission, // 成员缺少所需权限
+    InvalidPermissions,           // 权限位无效
+    #[msg("The signer is not allowed to execute proposals under the current executor policy.")]
+    ExecutorNotAllowed, // 执行者不符合策略
+    InvalidExecutors,             // 执行者列表无效
+    InvalidWeight,                // 投票权重无效
+    #[msg("The instruction data does not match any prefix allowed for this program.")]
+    InstructionNotAllowed, // 指令不在允许的前缀列表中
+    InvalidPrefix,                // 指令前缀无效
+    PrefixAlreadyAllowed,         // 指令前缀已存在
+    PrefixNotFound,               // 指令前缀不存在
+    TooManyPrefixes,              // 指令前缀数量过多
+    #[msg("Removing the last prefix would allow every instruction of the program.")]
+    CannotRemoveLastPrefix, // 无法移除最后一个前缀
+    #[msg("A writable account is not in the multisig's address book.")]
+    DestinationNotAllowed, // 可写账户不在地址簿中
+    AddressBookFull,              // 地址簿已满
+    AddressAlreadyInBook,         // 地址已在地址簿中
+    AddressNotInBook,             // 地址不在地址簿中
+    InvalidSpendingLimit,         // 支出限额配置无效
+    #[msg("The amount exceeds the remaining allowance for the current period.")]
+    SpendingLimitExceeded, // 超出支出限额
+    #[msg("The signer is not authorized to use this spending limit.")]
+    SpendingLimitMemberNotAllowed, // 成员无权使用支出限额
+    #[msg("The destination is not allowed by this spending limit.")]
+    SpendingLimitDestinationNotAllowed, // 目标地址不在支出限额允许范围内
+    #[msg("Token spending limits require matching mint, vault and destination token accounts.")]
+    InvalidSpendingLimitAccounts, // 支出限额账户无效
+    InvalidDestination,           // 收款地址无效
+    #[msg(
+        "The first remaining account must be the mint, owned by the Token or Token-2022 program."
+    )]
+    InvalidMint, // mint 账户无效
+    #[msg("The destination token account requires a memo for incoming transfers.")]
+    MemoRequired, // 目标代币账户要求转账备注
+    TooManyEphemeralSigners,      // 临时签名者数量过多
+    TransactionBufferTooLarge,    // 交易缓冲区超过最大长度
+    #[msg("The chunk would exceed the transaction buffer's declared final size.")]
+    TransactionBufferOverflow, // 数据块超出交易缓冲区声明的长度
+    TransactionBufferIncomplete,  // 交易缓冲区尚未上传完整
+    #[msg("The transaction buffer content does not match its declared hash.")]
+    TransactionBufferHashMismatch, // 交易缓冲区内容与哈希不符
+    InvalidTransactionBuffer,     // 交易缓冲区内容无法解析为指令列表
+    NotTransactionBufferCreator,  // 非交易缓冲区的创建者
+    #[msg("The transaction message header or an account index is out of range.")]
+    InvalidTransactionMessage, // 紧凑消息格式无效
+    #[msg("An address lookup table is missing, out of order or does not contain the referenced index.")]
+    InvalidAddressLookupTable, // 地址查找表无效
+    #[msg("The number of execute accounts does not match the proposal.")]
+    ExecuteAccountCountMismatch, // 执行账户数量与提案不符
+    #[msg("An execute account is not the proposal account at the same index.")]
+    ExecuteAccountKeyMismatch, // 执行账户与同一索引的提案账户不是同一地址
+    #[msg(
+        "An execute account's writable flag differs from the proposal account at the same index."
+    )]
+    ExecuteAccountWritableMismatch, // 执行账户的可写属性与提案账户不符
+    #[msg("An execute account signs although the proposal account at the same index does not.")]
+    ExecuteAccountSignerEscalation, // 执行账户提升了提案账户的签名属性
+    #[msg("This proposal executes step by step; use execute_step.")]
+    StepwiseExecutionRequired, // 分步执行的提案需使用 execute_step
+    #[msg("This proposal executes atomically; use execute.")]
+    AtomicExecutionRequired, // 原子执行的提案需使用 execute
+    TooManyBalanceAssertions,     // 余额断言数量过多
+    #[msg("A balance assertion must reference a proposal account and requires atomic execution.")]
+    InvalidBalanceAssertion, // 余额断言无效
+    #[msg("A balance assertion did not hold after execution.")]
+    BalanceAssertionFailed, // 执行后余额断言不成立
+    #[msg("The guard program is invalid or was not passed after the proposal accounts.")]
+    InvalidGuardProgram, // guard 程序无效或未按顺序传入
 }
 
 // (把它放在 lib.rs 文件的底部，`to_account_metas` 函数之后)
 fn is_pause_instruction(
-    ix: &InstructionData,
+    ix_program_id: &Pubkey,
+    data: &[u8],
     program_id: &Pubkey,
     expected_pause_state: bool,
 ) -> bool {
-    if ix.program_id != *program_id {
+    if ix_program_id != program_id {
         return false;
     }
     // Anchor 指令数据格式: 8字节 discriminator + 参数
-    if ix.data.len() != 9 {
+    if data.len() != 9 {
         return false;
     }
     // 手动计算或从 IDL 中获取 pause 指令的 discriminator
     // 假设我们已经知道它的 discriminator
     const PAUSE_IX_DISCRIMINATOR: [u8; 8] = [211, 22, 221, 251, 74, 121, 193, 47];
 
-    let discriminator = &ix.data[0..8];
-    let pause_state_byte = ix.data[8];
+    let discriminator = &data[0..8];
+    let pause_state_byte = data[8];
 
     discriminator == PAUSE_IX_DISCRIMINATOR && pause_state_byte == (expected_pause_state as u8)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 生成一组唯一公钥
+    fn keys<const N: usize>() -> [Pubkey; N] {
+        std::array::from_fn(|_| Pubkey::new_unique())
+    }
+
+    // 构造拥有全部权限的成员列表
+    fn members(keys: &[Pubkey]) -> Vec<Member> {
+        keys.iter()
+            .map(|key| Member {
+                key: *key,
+                permissions: PERMISSION_ALL,
+                weight: 1,
+            })
+            .collect()
+    }
+
+    // 构造包含指定程序及风险等级阈值的白名单
+    fn whitelist_with(entries: &[(Pubkey, u16)]) -> ProgramWhitelist {
+        ProgramWhitelist {
+            programs: entries
+                .iter()
+                .map(|&(program_id, required_approvals)| WhitelistEntry {
+                    program_id,
+                    required_approvals,
+                    allowed_prefixes: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    // 构造测试用的多签账户数据
+    fn multisig_with(owners: Vec<Member>, threshold: u16) -> Multisig {
+        Multisig {
+            bump: 0,
+            vault_bump: 0,
+            whitelist_bump: 0,
+            owners,
+            threshold,
+            next_tx_id: 0,
+            paused: false,
+            nonce: 0,
+            config_generation: 0,
+            time_lock_seconds: 0,
+            executor_policy: ExecutorPolicy::OwnersOnly,
+            executors: vec![],
+            config_threshold: threshold,
+            address_book_bump: 0,
+            guard_program: None,
+        }
+    }
+
+    // 构造测试用的交易账户数据，提案已获指定成员批准
+    fn transaction_with(message: TransactionMessage, approvals: Vec<Pubkey>) -> Transaction {
+        Transaction {
+            multisig: Pubkey::default(),
+            id: 0,
+            proposer: Pubkey::default(),
+            message,
+            approvals,
+            rejections: vec![],
+            expires_at: None,
+            config_generation: 0,
+            status: ProposalStatus::Approved,
+            timestamps: ProposalTimestamps::default(),
+            vault_index: 0,
+            vault_bump: 0,
+            ephemeral_signer_bumps: vec![],
+            execution_mode: ExecutionMode::Stepwise,
+            next_ix_index: 0,
+            assertions: vec![],
+        }
+    }
+
+    #[test]
+    fn vote_weight_counts_current_owners() {
+        let [a, b, c] = keys();
+        let ms = multisig_with(members(&[a, b, c]), 2);
+        assert_eq!(ms.vote_weight(&[a, c]), 2);
+        assert_eq!(ms.vote_weight(&[]), 0);
+    }
+
+    #[test]
+    fn vote_weight_ignores_non_owners() {
+        let [a, b] = keys();
+        let ms = multisig_with(members(&[a, b]), 2);
+        assert_eq!(ms.vote_weight(&[a, Pubkey::new_unique()]), 1);
+    }
+
+    #[test]
+    fn removed_owner_approval_does_not_reach_threshold() {
+        let [a, b, c] = keys();
+        let mut ms = multisig_with(members(&[a, b, c]), 2);
+        let approvals = vec![a, c];
+        assert!(ms.meets_threshold(&approvals, ms.threshold));
+
+        // 移除 owner c 后，其批准不再计入
+        ms.owners.retain(|m| m.key != c);
+        assert_eq!(ms.vote_weight(&approvals), 1);
+        assert!(!ms.meets_threshold(&approvals, ms.threshold));
+    }
+
+    #[test]
+    fn rejections_make_threshold_unreachable() {
+        let [a, b, c] = keys();
+        let ms = multisig_with(members(&[a, b, c]), 2);
+        assert!(ms.can_reach_threshold(&[a], ms.threshold));
+        assert!(!ms.can_reach_threshold(&[a, b], ms.threshold));
+    }
+
+    #[test]
+    fn removed_owner_rejection_is_ignored() {
+        let [a, b, c] = keys();
+        let ms = multisig_with(members(&[a, b]), 2);
+        assert!(ms.can_reach_threshold(&[c], ms.threshold));
+        assert!(!ms.can_reach_threshold(&[a, c], ms.threshold));
+    }
+
+    #[test]
+    fn votes_without_vote_permission_are_ignored() {
+        let [a, b, bot] = keys();
+        let mut owners = members(&[a, b]);
+        owners.push(Member {
+            key: bot,
+            permissions: PERMISSION_PROPOSE | PERMISSION_EXECUTE,
+            weight: 1,
+        });
+        let ms = multisig_with(owners, 2);
+        assert_eq!(ms.total_vote_weight(), 2);
+        assert_eq!(ms.vote_weight(&[a, bot]), 1);
+        assert!(!ms.can_reach_threshold(&[a], ms.threshold));
+    }
+
+    #[test]
+    fn weighted_votes_sum_toward_threshold() {
+        let [a, b, c] = keys();
+        let mut owners = members(&[a, b, c]);
+        owners[0].weight = 3;
+        let ms = multisig_with(owners, 3);
+        assert_eq!(ms.total_vote_weight(), 5);
+        assert!(ms.meets_threshold(&[a], ms.threshold));
+        assert!(!ms.meets_threshold(&[b, c], ms.threshold));
+        // a 拒绝后剩余权重 2 不足以达到阈值
+        assert!(!ms.can_reach_threshold(&[a], ms.threshold));
+        assert!(ms.can_reach_threshold(&[b], ms.threshold));
+    }
+
+    #[test]
+    fn config_changes_require_config_threshold() {
+        let [a, b, c] = keys();
+        let mut ms = multisig_with(members(&[a, b, c]), 1);
+        ms.config_threshold = 3;
+        let payment = system_program::ID;
+        let governance = crate::ID;
+        let whitelist = whitelist_with(&[]);
+        assert_eq!(ms.threshold_for(&whitelist, &[payment]), 1);
+        assert_eq!(ms.threshold_for(&whitelist, &[payment, governance]), 3);
+        // 配置变更阈值低于批准阈值时仍以批准阈值为准
+        ms.threshold = 2;
+        ms.config_threshold = 1;
+        assert_eq!(ms.threshold_for(&whitelist, &[payment]), 2);
+    }
+
+    #[test]
+    fn highest_whitelist_tier_applies() {
+        let [a, b, c, stake, treasury] = keys();
+        let ms = multisig_with(members(&[a, b, c]), 1);
+        let whitelist = whitelist_with(&[(system_program::ID, 0), (stake, 2), (treasury, 3)]);
+        assert_eq!(ms.threshold_for(&whitelist, &[system_program::ID]), 1);
+        assert_eq!(ms.threshold_for(&whitelist, &[stake]), 2);
+        assert_eq!(
+            ms.threshold_for(&whitelist, &[stake, treasury, system_program::ID]),
+            3
+        );
+    }
+
+    #[test]
+    fn whitelist_tiers_are_capped_at_remaining_vote_weight() {
+        let [a, b, c, treasury] = keys();
+        let mut ms = multisig_with(members(&[a, b, c]), 2);
+        ms.config_threshold = 2;
+        let whitelist = whitelist_with(&[(crate::ID, 3), (treasury, 3)]);
+        assert_eq!(ms.threshold_for(&whitelist, &[treasury]), 3);
+        // 移除一个 owner 后总权重为 2，等级为 3 的程序（包括本程序）仍可由剩余 owner 通过
+        ms.owners.retain(|m| m.key != c);
+        assert_eq!(ms.threshold_for(&whitelist, &[treasury]), 2);
+        assert_eq!(ms.threshold_for(&whitelist, &[crate::ID]), 2);
+        assert!(ms.meets_threshold(&[a, b], ms.threshold_for(&whitelist, &[crate::ID])));
+    }
+
+    #[test]
+    fn allowed_prefixes_filter_instruction_data() {
+        let [other] = keys();
+        let mut whitelist = whitelist_with(&[(system_program::ID, 0), (crate::ID, 0)]);
+        // 未配置前缀时允许所有指令
+        assert!(whitelist.allows_data(&system_program::ID, &[1, 0, 0, 0]));
+        // 仅允许 System Transfer（指令标签 2）
+        whitelist.programs[0].allowed_prefixes = vec![vec![2, 0, 0, 0]];
+        assert!(whitelist.allows_data(&system_program::ID, &[2, 0, 0, 0, 64, 0, 0, 0]));
+        assert!(!whitelist.allows_data(&system_program::ID, &[1, 0, 0, 0]));
+        assert!(!whitelist.allows_data(&system_program::ID, &[2]));
+        assert!(whitelist.allows_data(&crate::ID, &[9; 12]));
+        assert!(!whitelist.allows_data(&other, &[]));
+    }
+
+    #[test]
+    fn address_book_restricts_writable_accounts() {
+        let [ms, vault, payee, stranger] = keys();
+        let meta = |pubkey, is_writable| AccountMetaData {
+            pubkey,
+            is_signer: false,
+            is_writable,
+        };
+        let transfer_to = |to| InstructionData {
+            program_id: system_program::ID,
+            accounts: vec![meta(vault, true), meta(to, true), meta(stranger, false)],
+            data: vec![],
+        };
+        let mut book = AddressBook {
+            enabled: false,
+            addresses: vec![payee],
+        };
+        // 未启用时不限制
+        assert!(book.allows_writable(&transfer_to(stranger), &[ms, vault]));
+        book.enabled = true;
+        assert!(book.allows_writable(&transfer_to(payee), &[ms, vault]));
+        assert!(!book.allows_writable(&transfer_to(stranger), &[ms, vault]));
+        assert!(book.allows(&payee) && !book.allows(&stranger));
+    }
+
+    #[test]
+    fn program_instructions_only_write_derived_own_accounts() {
+        let [ms, vault, mint, stranger] = keys();
+        let (limit, _) = Pubkey::find_program_address(
+            &[b"spending_limit", ms.as_ref(), mint.as_ref()],
+            &crate::ID,
+        );
+        let limit_ix = |discriminator: &[u8], writable| InstructionData {
+            program_id: crate::ID,
+            accounts: vec![AccountMetaData {
+                pubkey: writable,
+                is_signer: false,
+                is_writable: true,
+            }],
+            data: [discriminator, mint.as_ref()].concat(),
+        };
+        let add = crate::instruction::AddSpendingLimit::DISCRIMINATOR;
+        let remove = crate::instruction::RemoveSpendingLimit::DISCRIMINATOR;
+        // 支出限额 PDA 由指令的 mint 参数推导，其他指令不推导
+        assert_eq!(
+            spending_limit_address(&limit_ix(add, limit), &ms),
+            Some(limit)
+        );
+        assert_eq!(
+            spending_limit_address(&limit_ix(remove, limit), &ms),
+            Some(limit)
+        );
+        assert_eq!(spending_limit_address(&limit_ix(&[0; 8], limit), &ms), None);
+        // 本程序的指令不再豁免地址簿，只能写入多签自身的账户
+        let book = AddressBook {
+            enabled: true,
+            addresses: vec![],
+        };
+        assert!(book.allows_writable(&limit_ix(add, limit), &[ms, vault, limit]));
+        assert!(!book.allows_writable(&limit_ix(add, stranger), &[ms, vault, limit]));
+
+        // 金库代币转账的金库代币账户和目标代币账户由程序自身约束和校验
+        let [address_book, vault_ata, destination] = keys();
+        let transfer = InstructionData {
+            program_id: crate::ID,
+            accounts: [ms, address_book, vault, mint, vault_ata, destination]
+                .into_iter()
+                .map(|pubkey| AccountMetaData {
+                    pubkey,
+                    is_signer: false,
+                    is_writable: pubkey == vault_ata || pubkey == destination,
+                })
+                .collect(),
+            data: crate::instruction::VaultTokenTransfer::DISCRIMINATOR.to_vec(),
+        };
+        assert_eq!(
+            vault_token_accounts(&transfer),
+            vec![vault_ata, destination]
+        );
+        assert!(vault_token_accounts(&limit_ix(add, limit)).is_empty());
+        assert!(!book.allows_writable(&transfer, &[ms, vault, vault_ata]));
+    }
+
+    #[test]
+    fn vault_token_source_is_the_signing_vaults_own_ata() {
+        let [vault, other_vault, mint, to] = keys();
+        let transfer_from = |owner: &Pubkey| -> InstructionData {
+            let source = get_associated_token_address_with_program_id(owner, &mint, &token::ID);
+            token_2022::spl_token_2022::instruction::transfer_checked(
+                &token::ID,
+                &source,
+                &mint,
+                &to,
+                owner,
+                &[],
+                1,
+                6,
+            )
+            .unwrap()
+            .into()
+        };
+        let vault_ata = get_associated_token_address_with_program_id(&vault, &mint, &token::ID);
+        // 只有所选金库自己的关联代币账户被视为自身账户，目标账户不受影响
+        assert_eq!(
+            vault_token_source(&transfer_from(&vault), &vault),
+            Some(vault_ata)
+        );
+        assert_eq!(
+            vault_token_source(&transfer_from(&other_vault), &vault),
+            None
+        );
+        let transfer: InstructionData = system_instruction::transfer(&vault, &to, 1).into();
+        assert_eq!(vault_token_source(&transfer, &vault), None);
+    }
+
+    #[test]
+    fn spending_limit_resets_each_period() {
+        let [ms, payee, stranger] = keys();
+        let day = SpendingPeriod::Day.seconds();
+        let mut limit = SpendingLimit {
+            multisig: ms,
+            mint: Pubkey::default(),
+            amount: 100,
+            period: SpendingPeriod::Day,
+            remaining_amount: 30,
+            last_reset: 1_000,
+            members: vec![],
+            destinations: vec![payee],
+            bump: 0,
+        };
+        assert!(limit.allows_destination(&payee));
+        assert!(!limit.allows_destination(&stranger));
+        // 同一周期内不重置
+        limit.refresh(1_000 + day - 1).unwrap();
+        assert_eq!(limit.remaining_amount, 30);
+        // 跨越多个周期后重置，且周期起点对齐
+        limit.refresh(1_000 + 2 * day + 5).unwrap();
+        assert_eq!(limit.remaining_amount, 100);
+        assert_eq!(limit.last_reset, 1_000 + 2 * day);
+    }
+
+    #[test]
+    fn sol_transfer_instruction_is_signed_by_vault_only() {
+        let [vault, to] = keys();
+        let ix: InstructionData = system_instruction::transfer(&vault, &to, 42).into();
+        assert_eq!(ix.program_id, system_program::ID);
+        assert!(is_signer_allowed(&ix, &[vault]));
+        assert!(!is_signer_allowed(&ix, &[to]));
+        assert!(ix.accounts[1].is_writable && !ix.accounts[1].is_signer);
+        assert!(validate_transfer_destination(&to, &vault).is_ok());
+        assert!(validate_transfer_destination(&vault, &vault).is_err());
+    }
+
+    #[test]
+    fn transaction_buffer_requires_complete_content_matching_hash() {
+        let [vault, to] = keys();
+        let instructions: Vec<InstructionData> = vec![
+            system_instruction::transfer(&vault, &to, 1).into(),
+            system_instruction::transfer(&vault, &to, 2).into(),
+        ];
+        let content = TransactionMessage::compile(&instructions)
+            .unwrap()
+            .try_to_vec()
+            .unwrap();
+        let mut buffer = TransactionBuffer {
+            multisig: Pubkey::default(),
+            creator: Pubkey::default(),
+            buffer_index: 0,
+            vault_index: 0,
+            final_hash: hash(&content).to_bytes(),
+            final_size: content.len() as u32,
+            buffer: content[..10].to_vec(),
+        };
+        assert!(buffer.validate().is_err());
+        buffer.buffer.extend_from_slice(&content[10..]);
+        assert!(buffer.validate().is_ok());
+        buffer.buffer[0] ^= 1;
+        assert!(buffer.validate().is_err());
+        // 交易账户按内容分配空间，远小于按最大指令数分配
+        let space = Transaction::space_for_message(content.len());
+        assert!(space < 8 + Transaction::INIT_SPACE);
+        let full = Transaction::space_for_message(TransactionMessage::INIT_SPACE);
+        assert_eq!(full, 8 + Transaction::INIT_SPACE);
+    }
+
+    #[test]
+    fn transaction_space_matches_serialized_instructions() {
+        let [vault, to, mint, source] = keys();
+        let sol: Vec<InstructionData> = vec![system_instruction::transfer(&vault, &to, 1).into()];
+        let message = TransactionMessage::compile(&sol).unwrap();
+        assert_eq!(
+            message.serialized_len(),
+            message.try_to_vec().unwrap().len()
+        );
+        assert_eq!(
+            Transaction::space(&sol),
+            Transaction::space_for_message(message.serialized_len())
+        );
+        // 转账快捷提案按单条 transfer_checked 指令分配的空间恰好容纳代币转账
+        let token: Vec<InstructionData> =
+            vec![token_2022::spl_token_2022::instruction::transfer_checked(
+                &token::ID,
+                &source,
+                &mint,
+                &to,
+                &vault,
+                &[],
+                1,
+                6,
+            )
+            .unwrap()
+            .into()];
+        assert_eq!(
+            Transaction::space(&token),
+            Transaction::space_for_message(TransactionMessage::single_instruction_space(4, 10))
+        );
+        assert!(Transaction::space(&sol) < Transaction::space(&token));
+    }
+
+    #[test]
+    fn transaction_message_deduplicates_accounts_and_round_trips() {
+        let [vault, to, other] = keys();
+        let instructions: Vec<InstructionData> = vec![
+            system_instruction::transfer(&vault, &to, 1).into(),
+            system_instruction::transfer(&vault, &other, 2).into(),
+        ];
+        let message = TransactionMessage::compile(&instructions).unwrap();
+        // 金库、两个收款地址和系统程序各只存一次，可写签名账户排在最前
+        assert_eq!(message.account_keys.len(), 4);
+        assert_eq!(message.account_keys[0], vault);
+        assert_eq!(
+            (
+                message.num_signers,
+                message.num_writable_signers,
+                message.num_writable_non_signers
+            ),
+            (1, 1, 2)
+        );
+        let restored = message.instructions(&LoadedAddresses::default()).unwrap();
+        assert_eq!(
+            restored.try_to_vec().unwrap(),
+            instructions.try_to_vec().unwrap()
+        );
+        // 紧凑消息比逐条存放账户元数据更小
+        assert!(message.serialized_len() < instructions.try_to_vec().unwrap().len());
+        // 消息头或账户索引越界时拒绝
+        let mut invalid = message.clone();
+        invalid.num_signers = 5;
+        assert!(invalid.instructions(&LoadedAddresses::default()).is_err());
+        let mut invalid = message;
+        invalid.instructions[0].account_indexes[1] = 4;
+        assert!(invalid.instructions(&LoadedAddresses::default()).is_err());
+    }
+
+    #[test]
+    fn lookup_table_indexes_resolve_after_static_keys() {
+        let [vault, to, mint, table] = keys();
+        // 地址查找表数据：元数据之后依次存放地址
+        let mut data = vec![0; LOOKUP_TABLE_META_SIZE];
+        data.extend_from_slice(to.as_ref());
+        data.extend_from_slice(mint.as_ref());
+        assert_eq!(lookup_table_address(&data, 1).unwrap(), mint);
+        assert!(lookup_table_address(&data, 2).is_err());
+
+        let mut message =
+            TransactionMessage::compile(&[system_instruction::transfer(&vault, &to, 1).into()])
+                .unwrap();
+        message
+            .address_table_lookups
+            .push(MessageAddressTableLookup {
+                account_key: table,
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            });
+        let loaded = LoadedAddresses {
+            writable: vec![to],
+            readonly: vec![mint],
+        };
+        // 静态账户表为金库、收款地址和系统程序，之后是查找表中的可写、只读账户
+        let num_keys = message.account_keys.len() as u8;
+        let writable = message.account_meta(num_keys, &loaded).unwrap();
+        assert!(writable.pubkey == to && writable.is_writable && !writable.is_signer);
+        let readonly = message.account_meta(num_keys + 1, &loaded).unwrap();
+        assert!(readonly.pubkey == mint && !readonly.is_writable && !readonly.is_signer);
+        assert!(message.account_meta(num_keys + 2, &loaded).is_err());
+        // 程序 ID 不能来自查找表
+        message.instructions[0].program_id_index = num_keys;
+        assert!(message.program_ids().is_err());
+    }
+
+    #[test]
+    fn execute_accounts_must_match_message_without_escalation() {
+        let [vault, to, other] = keys();
+        let message =
+            TransactionMessage::compile(&[system_instruction::transfer(&vault, &to, 1).into()])
+                .unwrap();
+        let loaded = LoadedAddresses::default();
+        // 金库以 PDA 签名，客户端传入时不带签名
+        assert!(message
+            .check_account(0, &loaded, &vault, false, true)
+            .is_ok());
+        assert!(message.check_account(1, &loaded, &to, false, true).is_ok());
+        // 替换账户、提升可写或签名属性分别返回对应错误，并在错误来源中记录索引
+        let mismatch = |result: Result<()>| match result.unwrap_err() {
+            Error::AnchorError(e) => match e.error_origin {
+                Some(ErrorOrigin::AccountName(name)) => (e.error_name, name),
+                origin => panic!("unexpected error origin: {origin:?}"),
+            },
+            other => panic!("unexpected error: {other:?}"),
+        };
+        assert_eq!(
+            mismatch(message.check_account(1, &loaded, &other, false, true)),
+            (
+                "ExecuteAccountKeyMismatch".to_string(),
+                "execute_accounts[1]".to_string()
+            )
+        );
+        let system = system_program::ID;
+        assert!(message
+            .check_account(2, &loaded, &system, false, false)
+            .is_ok());
+        assert_eq!(
+            mismatch(message.check_account(2, &loaded, &system, false, true)),
+            (
+                "ExecuteAccountWritableMismatch".to_string(),
+                "execute_accounts[2]".to_string()
+            )
+        );
+        assert_eq!(
+            mismatch(message.check_account(1, &loaded, &to, true, true)),
+            (
+                "ExecuteAccountSignerEscalation".to_string(),
+                "execute_accounts[1]".to_string()
+            )
+        );
+        // 超出账户表的索引被拒绝
+        assert!(message
+            .check_account(3, &loaded, &other, false, false)
+            .is_err());
+    }
+
+    #[test]
+    fn ephemeral_signers_may_sign_their_own_creation() {
+        let [vault, transaction, other_transaction] = keys();
+        let (signer, _) = ephemeral_signer_address(&transaction, 0);
+        assert_ne!(signer, ephemeral_signer_address(&transaction, 1).0);
+        assert_ne!(signer, ephemeral_signer_address(&other_transaction, 0).0);
+        let ix: InstructionData =
+            system_instruction::create_account(&vault, &signer, 1, 0, &system_program::ID).into();
+        assert!(!is_signer_allowed(&ix, &[vault]));
+        assert!(is_signer_allowed(&ix, &[vault, signer]));
+    }
+
+    #[test]
+    fn vault_index_zero_is_the_legacy_vault() {
+        let [multisig_key] = keys();
+        let (legacy, bump) =
+            Pubkey::find_program_address(&[b"vault", multisig_key.as_ref()], &crate::ID);
+        let mut ms = multisig_with(members(&keys::<1>()), 1);
+        ms.vault_bump = bump;
+        assert_eq!(ms.vault_address(&multisig_key, 0).unwrap(), (legacy, bump));
+        let (reserve, _) = ms.vault_address(&multisig_key, 1).unwrap();
+        let (payroll, _) = ms.vault_address(&multisig_key, 2).unwrap();
+        assert_ne!(reserve, legacy);
+        assert_ne!(reserve, payroll);
+    }
+
+    #[test]
+    fn executor_policy_controls_who_can_execute() {
+        let [a, bot, outsider] = keys();
+        let mut owners = members(&[a]);
+        owners.push(Member {
+            key: bot,
+            permissions: PERMISSION_PROPOSE,
+            weight: 1,
+        });
+        let mut ms = multisig_with(owners, 1);
+        assert!(ms.can_execute(&a));
+        assert!(!ms.can_execute(&bot));
+        assert!(!ms.can_execute(&outsider));
+
+        ms.executor_policy = ExecutorPolicy::Designated;
+        ms.executors = vec![outsider];
+        assert!(!ms.can_execute(&a));
+        assert!(ms.can_execute(&outsider));
+
+        ms.executor_policy = ExecutorPolicy::Anyone;
+        assert!(ms.can_execute(&bot));
+    }
+
+    #[test]
+    fn execution_checks_apply_before_the_first_step() {
+        let [a, b, vault, to] = keys();
+        let ms = multisig_with(members(&[a, b]), 2);
+        let whitelist = whitelist_with(&[]);
+        let message = TransactionMessage::compile(&[
+            system_instruction::transfer(&vault, &to, 1).into(),
+            system_instruction::transfer(&vault, &to, 2).into(),
+        ])
+        .unwrap();
+        let mut tx = transaction_with(message, vec![a, b]);
+        assert!(require_executable(&ms, &whitelist, &tx, 0).is_ok());
+        // 过期、配置变更或批准不足时不能开始执行
+        tx.expires_at = Some(-1);
+        assert!(require_executable(&ms, &whitelist, &tx, 0).is_err());
+        tx.expires_at = None;
+        tx.config_generation = 1;
+        assert!(require_executable(&ms, &whitelist, &tx, 0).is_err());
+        tx.config_generation = 0;
+        tx.approvals = vec![a];
+        assert!(require_executable(&ms, &whitelist, &tx, 0).is_err());
+        // 进入执行中状态后，继续执行只检查暂停和配置代数
+        tx.transition_to(ProposalStatus::Executing, 0).unwrap();
+        assert!(tx.require_approved().is_err());
+        assert!(!tx.status.is_final());
+        assert!(require_continuable(&ms, &tx).is_ok());
+        tx.config_generation = 1;
+        assert!(require_continuable(&ms, &tx).is_err());
+        // 执行中的提案可以完成，也可以取消以中止剩余步骤，取消后可以关闭
+        assert!(ProposalStatus::Executing.can_transition_to(ProposalStatus::Executed));
+        assert!(tx.transition_to(ProposalStatus::Cancelled, 0).is_ok());
+        assert!(tx.status.is_final());
+    }
+
+    #[test]
+    fn balance_assertions_bound_decrease_and_final_balance() {
+        let [vault, token_account] = keys();
+        let max_decrease = BalanceAssertion::MaxLamportDecrease {
+            account: vault,
+            max_decrease: 100,
+        };
+        assert_eq!(max_decrease.account(), &vault);
+        assert!(max_decrease.holds(1_000, 900));
+        assert!(max_decrease.holds(1_000, 2_000));
+        assert!(!max_decrease.holds(1_000, 899));
+        let min_balance = BalanceAssertion::MinTokenBalance {
+            token_account,
+            min_amount: 50,
+        };
+        assert!(min_balance.holds(0, 50));
+        assert!(!min_balance.holds(100, 49));
+    }
+
+    #[test]
+    fn guard_hooks_receive_instructions_and_skip_guard_updates() {
+        let [vault, to, guard] = keys();
+        let instructions: Vec<InstructionData> =
+            vec![system_instruction::transfer(&vault, &to, 1).into()];
+        // 钩子数据为 Anchor 规则的 discriminator 加指令列表
+        let data = guard_hook_data(GUARD_PRE_EXECUTE, &instructions).unwrap();
+        assert_eq!(data[..8], hash(b"global:pre_execute").to_bytes()[..8]);
+        assert_ne!(
+            data[..8],
+            guard_hook_data(GUARD_POST_EXECUTE, &instructions).unwrap()[..8]
+        );
+        let decoded = Vec::<InstructionData>::try_from_slice(&data[8..]).unwrap();
+        assert_eq!(
+            decoded.try_to_vec().unwrap(),
+            instructions.try_to_vec().unwrap()
+        );
+
+        // 仅包含设置 guard 指令的提案不经过钩子
+        let message = TransactionMessage::compile(&instructions).unwrap();
+        assert!(!transaction_with(message, vec![])
+            .is_guard_update_proposal()
+            .unwrap());
+        let mut set_guard = crate::instruction::SetGuardProgram::DISCRIMINATOR.to_vec();
+        Some(guard).serialize(&mut set_guard).unwrap();
+        let message = TransactionMessage::compile(&[InstructionData {
+            program_id: crate::ID,
+            accounts: vec![],
+            data: set_guard,
+        }])
+        .unwrap();
+        assert!(transaction_with(message, vec![])
+            .is_guard_update_proposal()
+            .unwrap());
+    }
+
+    #[test]
+    fn final_statuses_cannot_transition() {
+        use ProposalStatus::*;
+        let all = [
+            Draft, Active, Approved, Rejected, Executing, Executed, Cancelled, Expired,
+        ];
+        for from in all.into_iter().filter(|s| s.is_final()) {
+            assert!(all.iter().all(|to| !from.can_transition_to(*to)));
+        }
+        assert!(Approved.can_transition_to(Active));
+        assert!(!Active.can_transition_to(Executed));
+    }
+
+    #[test]
+    fn removed_then_readded_owner_approval_counts_again() {
+        let [a, b, c] = keys();
+        let mut ms = multisig_with(members(&[a, b, c]), 2);
+        let approvals = vec![a, c];
+
+        ms.owners.retain(|m| m.key != c);
+        ms.owners.extend(members(&[c]));
+        assert_eq!(ms.vote_weight(&approvals), 2);
+    }
+}
@@ -7,6 +7,12 @@ import type { Multisig } from '../target/types/multisig'
 // Re-export the generated IDL and type
 export { Multisig, MultisigIDL }
 
+// Member permission bits, mirroring the on-chain PERMISSION_* constants.
+export const PERMISSION_PROPOSE = 1 << 0
+export const PERMISSION_VOTE = 1 << 1
+export const PERMISSION_EXECUTE = 1 << 2
+export const PERMISSION_ALL = PERMISSION_PROPOSE | PERMISSION_VOTE | PERMISSION_EXECUTE
+
 // The programId is imported from the program IDL.
 export const MULTISIG_PROGRAM_ID = new PublicKey(MultisigIDL.address)
 
@@ -9,6 +9,7 @@ import { Multisig } from '../target/types/multisig'
 
 // 导入 Solana Web3.js 库的必要组件，用于密钥对、系统程序、交易等操作
 import {
+  AddressLookupTableProgram, // 地址查找表程序，用于创建和扩展查找表
   Keypair, // 用于生成和管理密钥对
   SystemProgram, // 系统程序，处理账户创建和转账等操作
   PublicKey, // 表示 Solana 公钥
@@ -16,8 +17,25 @@ import {
   Transaction as Web3Transaction, // Web3.js 的交易类
   sendAndConfirmTransaction, // 发送并确认交易的工具函数
   SendTransactionError, // 交易错误处理
+  TransactionInstruction, // Web3.js 的指令类
 } from '@solana/web3.js'
 
+// 导入 SPL Token 库的辅助函数，用于创建测试代币和代币账户
+import {
+  TOKEN_PROGRAM_ID, // Token 程序 ID
+  createAssociatedTokenAccount, // 创建关联代币账户
+  createMint, // 创建代币 mint
+  getAccount, // 读取代币账户
+  getAssociatedTokenAddressSync, // 推导关联代币账户地址
+  mintTo, // 铸造代币
+} from '@solana/spl-token'
+
+// 导入 Node.js 的哈希函数，用于计算交易缓冲区内容的哈希
+import { createHash } from 'crypto'
+
+// Memo 程序 ID，要求传入的账户全部签名，用作总是拒绝的 guard 程序
+const MEMO_PROGRAM_ID = new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr')
+
 // 定义主测试套件，测试多签钱包功能
 describe('multisig', () => {
   // 配置 Anchor 客户端连接到本地 Solana 集群（如 solana-test-validator）
@@ -40,6 +58,16 @@ describe('multisig', () => {
   let whitelistPda: PublicKey // 白名单账户的 PDA
   const nonce = new BN(0) // 用于 PDA 生成的初始 nonce 值
 
+  // 成员权限位，与链上常量保持一致
+  const PERMISSION_PROPOSE = 1 << 0 // 发起提案
+  const PERMISSION_VOTE = 1 << 1 // 投票
+  const PERMISSION_EXECUTE = 1 << 2 // 执行提案
+  const PERMISSION_ALL = PERMISSION_PROPOSE | PERMISSION_VOTE | PERMISSION_EXECUTE // 全部权限
+
+  // 将公钥列表转换为拥有指定权限和权重的成员列表
+  const asMembers = (keys: PublicKey[], permissions = PERMISSION_ALL, weight = 1) =>
+    keys.map((key) => ({ key, permissions, weight }))
+
   // 在所有测试开始前执行一次初始化
   beforeAll(async () => {
     // 生成三个所有者的密钥对
@@ -82,6 +110,18 @@ describe('multisig', () => {
     return { multisigPda: msPda, vaultPda: vPda, whitelistPda: wlPda }
   }
 
+  // 按提案消息的账户表顺序构造 execute 所需的剩余账户，链上要求逐一对应
+  const executeAccounts = async (txPda: PublicKey) => {
+    const { message } = await program.account.transaction.fetch(txPda)
+    return message.accountKeys.map((pubkey, index) => ({
+      pubkey, // 账户公钥
+      isSigner: false, // 金库和临时签名者由链上 invoke_signed 签名
+      isWritable:
+        index < message.numWritableSigners ||
+        (index >= message.numSigners && index < message.numSigners + message.numWritableNonSigners),
+    }))
+  }
+
   // 测试套件：创建多签钱包
   describe('create_multisig', () => {
     // 测试用例：成功创建 2/3 多签钱包
@@ -98,7 +138,7 @@ describe('multisig', () => {
 
       // 调用程序的 createMultisig 方法创建多签钱包
       await program.methods
-        .createMultisig(ownerPubkeys, threshold, nonce) // 设置所有者、阈值和 nonce
+        .createMultisig(asMembers(ownerPubkeys), threshold, nonce) // 设置成员、阈值和 nonce
         .accounts({
           multisig: multisigPda, // 多签账户
           vault: vaultPda, // 金库账户
@@ -111,7 +151,9 @@ describe('multisig', () => {
       // 验证多签账户状态
       const multisigAccount = await program.account.multisig.fetch(multisigPda)
       // 确认所有者列表正确
-      expect(multisigAccount.owners.map((o) => o.toString())).toEqual(ownerPubkeys.map((o) => o.toString()))
+      expect(multisigAccount.owners.map((o) => o.key.toString())).toEqual(ownerPubkeys.map((o) => o.toString()))
+      // 确认成员拥有全部权限
+      expect(multisigAccount.owners.every((o) => o.permissions === PERMISSION_ALL)).toBe(true)
       // 确认阈值正确
       expect(multisigAccount.threshold).toBe(threshold)
       // 确认 nonce 正确
@@ -155,7 +197,7 @@ describe('multisig', () => {
       // 期望创建多签失败，抛出 DuplicateOwners 错误
       await expect(
         program.methods
-          .createMultisig(duplicateOwners, 2, localNonce)
+          .createMultisig(asMembers(duplicateOwners), 2, localNonce)
           .accountsPartial({ multisig: newMs, payer: payer.publicKey })
           .rpc(),
       ).rejects.toThrow(/DuplicateOwners/)
@@ -170,7 +212,7 @@ describe('multisig', () => {
       await expect(
         program.methods
           .createMultisig(
-            owners.map((o) => o.publicKey),
+            asMembers(owners.map((o) => o.publicKey)),
             4,
             localNonce,
           )
@@ -188,7 +230,7 @@ describe('multisig', () => {
       await expect(
         program.methods
           .createMultisig(
-            owners.map((o) => o.publicKey),
+            asMembers(owners.map((o) => o.publicKey)),
             0,
             localNonce,
           )
@@ -237,7 +279,7 @@ describe('multisig', () => {
 
       // 提案交易
       await program.methods
-        .propose([ixData], null, false) // 无过期时间，不自动批准
+        .propose([ixData], null, false, 0, 0, { atomic: {} }, []) // 无过期时间，不自动批准
         .accounts({
           multisig: multisigPda, // 多签账户
           whitelist: whitelistPda, // 白名单账户
@@ -254,7 +296,7 @@ describe('multisig', () => {
       expect(txAccount.proposer.equals(ownerA.publicKey)).toBe(true) // 确认提案者
       expect(txAccount.multisig.equals(multisigPda)).toBe(true) // 确认多签账户
       expect(txAccount.approvals).toHaveLength(0) // 确认无批准
-      expect(txAccount.executed).toBe(false) // 确认未执行
+      expect(txAccount.status).toEqual({ active: {} }) // 确认处于投票阶段
 
       // 验证多签账户的交易 ID 已递增
       const multisigAccountAfter = await program.account.multisig.fetch(multisigPda)
@@ -330,6 +372,28 @@ describe('multisig', () => {
         .signers([ownerB])
         .rpc()
 
+      // 账户缺失、被替换或权限被提升时拒绝执行
+      const executeWith = (
+        accounts: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[],
+        signers = [ownerA],
+      ) =>
+        program.methods
+          .execute()
+          .accounts({ multisig: multisigPda, transaction: transactionPda, executor: ownerA.publicKey })
+          .remainingAccounts(accounts)
+          .signers(signers)
+          .rpc()
+      const vaultMeta = { pubkey: vaultPda, isSigner: false, isWritable: true }
+      const recipientMeta = { pubkey: recipient.publicKey, isSigner: false, isWritable: true }
+      const systemMeta = { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }
+      await expect(executeWith([vaultMeta, recipientMeta])).rejects.toThrow(/ExecuteAccountCountMismatch/)
+      await expect(
+        executeWith([vaultMeta, { ...recipientMeta, pubkey: ownerC.publicKey }, systemMeta]),
+      ).rejects.toThrow(/ExecuteAccountKeyMismatch/)
+      await expect(
+        executeWith([vaultMeta, { ...recipientMeta, isSigner: true }, systemMeta], [ownerA, recipient]),
+      ).rejects.toThrow(/ExecuteAccountSignerEscalation/)
+
       // 获取接收者账户余额
       const recipientBalanceBefore = await provider.connection.getBalance(recipient.publicKey)
 
@@ -339,17 +403,20 @@ describe('multisig', () => {
         .accounts({
           multisig: multisigPda,
           transaction: transactionPda,
+          executor: ownerA.publicKey, // 执行者
         })
         .remainingAccounts([
           { pubkey: vaultPda, isSigner: false, isWritable: true }, // 金库账户
           { pubkey: recipient.publicKey, isSigner: false, isWritable: true }, // 接收者账户
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // 系统程序
         ])
+        .signers([ownerA])
         .rpc()
 
       // 验证交易已执行
       const txAccountAfter = await program.account.transaction.fetch(transactionPda)
-      expect(txAccountAfter.executed).toBe(true)
+      expect(txAccountAfter.status).toEqual({ executed: {} })
+      expect(txAccountAfter.timestamps.executedAt).not.toBeNull()
 
       // 验证接收者余额增加
       const recipientBalanceAfter = await provider.connection.getBalance(recipient.publicKey)
@@ -365,12 +432,14 @@ describe('multisig', () => {
           .accounts({
             multisig: multisigPda,
             transaction: transactionPda,
+            executor: ownerA.publicKey,
           })
           .remainingAccounts([
             { pubkey: vaultPda, isSigner: false, isWritable: true },
             { pubkey: recipient.publicKey, isSigner: false, isWritable: true },
             { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
           ])
+          .signers([ownerA])
           .rpc(),
       ).rejects.toThrow(/AlreadyExecuted/) // 抛出 AlreadyExecuted 错误
     })
@@ -385,7 +454,7 @@ describe('multisig', () => {
         .accounts({
           multisig: multisigPda,
           transaction: transactionPda,
-          recipient: rentRecipient.publicKey, // 租金接收者
+          closer: rentRecipient.publicKey, // 关闭者
           authorizedCloser: rentRecipient.publicKey, // 授权关闭者
         })
         .signers([rentRecipient])
@@ -411,7 +480,7 @@ describe('multisig', () => {
       // 创建新的多签钱包
       await program.methods
         .createMultisig(
-          owners.map((o) => o.publicKey),
+          asMembers(owners.map((o) => o.publicKey)),
           2,
           newNonce,
         )
@@ -481,7 +550,7 @@ describe('multisig', () => {
 
       // 提案并自动批准
       await program.methods
-        .propose([dummyInstruction], null, true) // autoApprove = true
+        .propose([dummyInstruction], null, true, 0, 0, { atomic: {} }, []) // autoApprove = true
         .accounts({
           multisig: multisigPda,
           whitelist: whitelistPda,
@@ -542,7 +611,7 @@ describe('multisig', () => {
       // 提案
       try {
         await program.methods
-          .propose([dummyInstruction], null, false)
+          .propose([dummyInstruction], null, false, 0, 0, { atomic: {} }, [])
           .accounts({
             multisig: multisigPda,
             whitelist: whitelistPda,
@@ -564,7 +633,7 @@ describe('multisig', () => {
       console.log('Transaction account:', txAccount)
       expect(txAccount.proposer.equals(ownerB.publicKey)).toBe(true)
       expect(txAccount.multisig.equals(multisigPda)).toBe(true)
-      expect(txAccount.executed).toBe(false)
+      expect(txAccount.status).toEqual({ active: {} })
 
       // 取消提案
       try {
@@ -583,10 +652,84 @@ describe('multisig', () => {
         throw err
       }
 
+      // 验证提案已标记为取消，账户仍保留
+      const cancelledTx = await program.account.transaction.fetch(txPda)
+      expect(cancelledTx.status).toEqual({ cancelled: {} })
+      expect(cancelledTx.timestamps.cancelledAt).not.toBeNull()
+
+      // 关闭已取消的提案回收租金
+      await program.methods
+        .closeTransaction()
+        .accounts({
+          multisig: multisigPda,
+          transaction: txPda,
+          closer: ownerB.publicKey,
+          authorizedCloser: ownerB.publicKey,
+        })
+        .signers([ownerB])
+        .rpc()
+
       // 验证交易账户已关闭
       await expect(program.account.transaction.fetch(txPda)).rejects.toThrow()
     })
 
+    it('应该按实际指令分配交易账户空间，并在关闭时向提议者退还租金', async () => {
+      const dummyInstruction = {
+        programId: SystemProgram.programId,
+        accounts: [
+          { pubkey: vaultPda, isSigner: true, isWritable: true },
+          { pubkey: payer.publicKey, isSigner: false, isWritable: true },
+        ],
+        data: SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: payer.publicKey, lamports: 1000 }).data,
+      }
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      await program.methods
+        .propose([dummyInstruction], null, false, 0, 0, { atomic: {} }, [])
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+
+      // 交易账户空间远小于按最大指令数分配的空间，租金恰好满足免租要求
+      const txInfo = await provider.connection.getAccountInfo(txPda)
+      const rent = await provider.connection.getMinimumBalanceForRentExemption(txInfo!.data.length)
+      expect(txInfo!.data.length).toBeLessThan(program.account.transaction.size / 4)
+      expect(txInfo!.lamports).toBe(rent)
+
+      await program.methods
+        .cancelProposal()
+        .accounts({ multisig: multisigPda, transaction: txPda, proposer: ownerA.publicKey })
+        .signers([ownerA])
+        .rpc()
+
+      // 由其他 owner 关闭时，租金仍退还给支付租金的提议者
+      const proposerBalance = await provider.connection.getBalance(ownerA.publicKey)
+      const closerBalance = await provider.connection.getBalance(ownerB.publicKey)
+      await program.methods
+        .closeTransaction()
+        .accounts({
+          multisig: multisigPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          closer: ownerB.publicKey,
+          authorizedCloser: ownerB.publicKey,
+        })
+        .signers([ownerB])
+        .rpc()
+      expect(await provider.connection.getBalance(ownerA.publicKey)).toBe(proposerBalance + rent)
+      expect(await provider.connection.getBalance(ownerB.publicKey)).toBe(closerBalance)
+    })
+
     // 测试用例：拒绝过期交易的批准
     it('当交易过期后，应该拒绝批准', async () => {
       // 定义转账指令
@@ -618,7 +761,7 @@ describe('multisig', () => {
 
       // 提案交易
       await program.methods
-        .propose([dummyInstruction], expiresAt, false)
+        .propose([dummyInstruction], expiresAt, false, 0, 0, { atomic: {} }, [])
         .accounts({
           multisig: multisigPda,
           whitelist: whitelistPda,
@@ -645,38 +788,120 @@ describe('multisig', () => {
           .signers([ownerB])
           .rpc(),
       ).rejects.toThrow(/Expired/)
+
+      // 关闭时过期提案被标记为过期状态并回收租金
+      await program.methods
+        .closeTransaction()
+        .accounts({
+          multisig: multisigPda,
+          transaction: txPda,
+          closer: ownerA.publicKey,
+          authorizedCloser: ownerA.publicKey,
+        })
+        .signers([ownerA])
+        .rpc()
+      await expect(program.account.transaction.fetch(txPda)).rejects.toThrow()
     }, 10000) // 设置测试超时为 10 秒
-  })
 
-  // 测试套件：管理功能（通过提案、批准、执行流程）
-  describe('Management Functions', () => {
-    // 辅助函数：执行包含管理指令的交易
-    const executeTxWithInstruction = async (instructionPromise, remainingAccounts) => {
-      // 获取多签账户状态
-      const multisigAccount = await program.account.multisig.fetch(multisigPda)
-      const txId = multisigAccount.nextTxId
-      // 计算交易 PDA
+    // 测试用例：成员只能执行其权限允许的操作
+    it('应该按成员权限限制提案、投票和执行', async () => {
+      // 创建包含仅提案机器人的多签：ownerA、ownerB 拥有全部权限，notAnOwner 只能提案
+      const localNonce = new BN(Date.now() + Math.floor(Math.random() * 1000000))
+      const { multisigPda: ms, vaultPda: vault, whitelistPda: wl } = findPdas(payer.publicKey, localNonce)
+      await program.methods
+        .createMultisig(
+          [...asMembers([ownerA.publicKey, ownerB.publicKey]), { key: notAnOwner.publicKey, permissions: PERMISSION_PROPOSE, weight: 1 }],
+          2,
+          localNonce,
+        )
+        .accountsPartial({ multisig: ms, vault, whitelist: wl, payer: payer.publicKey })
+        .rpc()
+
+      // 阈值不能超过拥有投票权的成员数量
+      const badNonce = new BN(localNonce.toNumber() + 1)
+      const { multisigPda: badMs } = findPdas(payer.publicKey, badNonce)
+      await expect(
+        program.methods
+          .createMultisig(
+            [...asMembers([ownerA.publicKey, ownerB.publicKey]), { key: notAnOwner.publicKey, permissions: PERMISSION_PROPOSE, weight: 1 }],
+            3,
+            badNonce,
+          )
+          .accountsPartial({ multisig: badMs, payer: payer.publicKey })
+          .rpc(),
+      ).rejects.toThrow(/InvalidThreshold/)
+
+      // 机器人提案成功
       const [txPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('tx'), multisigPda.toBuffer(), txId.toBuffer('le', 8)],
+        [Buffer.from('tx'), ms.toBuffer(), new BN(0).toBuffer('le', 8)],
         program.programId,
       )
+      const ix = {
+        programId: SystemProgram.programId,
+        accounts: [
+          { pubkey: vault, isSigner: true, isWritable: true },
+          { pubkey: payer.publicKey, isSigner: false, isWritable: true },
+        ],
+        data: SystemProgram.transfer({ fromPubkey: vault, toPubkey: payer.publicKey, lamports: 1000 }).data,
+      }
+      await program.methods
+        .propose([ix], null, false, 0, 0, { atomic: {} }, [])
+        .accountsPartial({ multisig: ms, whitelist: wl, vault, transaction: txPda, proposer: notAnOwner.publicKey })
+        .signers([notAnOwner])
+        .rpc()
 
-      // 获取指令数据
-      const ix = await instructionPromise.instruction()
-      const ixData = {
-        programId: program.programId,
-        accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
-        data: ix.data,
+      // 机器人没有投票权限
+      await expect(
+        program.methods
+          .approve()
+          .accounts({ multisig: ms, transaction: txPda, owner: notAnOwner.publicKey })
+          .signers([notAnOwner])
+          .rpc(),
+      ).rejects.toThrow(/MissingPermission/)
+
+      // 机器人没有执行权限
+      for (const owner of [ownerA, ownerB]) {
+        await program.methods
+          .approve()
+          .accounts({ multisig: ms, transaction: txPda, owner: owner.publicKey })
+          .signers([owner])
+          .rpc()
       }
-      // 设置客户端剩余账户，禁用签名
-      const clientSideRemainingAccounts = remainingAccounts.map((acc) => ({
-        ...acc,
-        isSigner: false,
-      }))
+      await expect(
+        program.methods
+          .execute()
+          .accounts({ multisig: ms, transaction: txPda, executor: notAnOwner.publicKey })
+          .signers([notAnOwner])
+          .rpc(),
+      ).rejects.toThrow(/ExecutorNotAllowed/)
+    })
 
-      // 提案并由 ownerA 自动批准
+    // 测试用例：拒绝票使阈值无法达到时，提案被永久拒绝
+    it('当拒绝票使阈值无法达到时应该拒绝提案，并允许关闭', async () => {
+      // 定义转账指令
+      const dummyInstruction = {
+        programId: SystemProgram.programId,
+        accounts: [
+          { pubkey: vaultPda, isSigner: true, isWritable: true },
+          { pubkey: payer.publicKey, isSigner: false, isWritable: true },
+        ],
+        data: SystemProgram.transfer({
+          fromPubkey: vaultPda,
+          toPubkey: payer.publicKey,
+          lamports: 1000000,
+        }).data,
+      }
+
+      // 计算交易 PDA
+      const multisigAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), multisigAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+
+      // ownerA 提案并自动批准
       await program.methods
-        .propose([ixData], null, true)
+        .propose([dummyInstruction], null, true, 0, 0, { atomic: {} }, [])
         .accounts({
           multisig: multisigPda,
           whitelist: whitelistPda,
@@ -688,93 +913,1467 @@ describe('multisig', () => {
         .signers([ownerA])
         .rpc()
 
-      // ownerB 批准以达到阈值
+      // ownerB 拒绝，剩余 owner 仍可达到阈值
       await program.methods
-        .approve()
+        .reject()
         .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
         .signers([ownerB])
         .rpc()
+      let txAccount = await program.account.transaction.fetch(txPda)
+      expect(txAccount.rejections).toHaveLength(1)
+      expect(txAccount.status).toEqual({ active: {} })
 
-      // 执行交易
+      // ownerA 改投拒绝票，阈值已不可能达到
       await program.methods
-        .execute()
-        .accounts({ multisig: multisigPda, transaction: txPda })
-        .remainingAccounts(clientSideRemainingAccounts)
+        .reject()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerA.publicKey })
+        .signers([ownerA])
         .rpc()
-    }
-
-    // 测试用例：修改阈值
-    it('应该成功修改阈值', async () => {
-      const newThreshold = 1 // 新阈值
-      // 定义修改阈值指令
-      const changeThresholdIx = program.methods
-        .changeThreshold(newThreshold)
-        .accounts({ multisig: multisigPda, vault: vaultPda })
-
-      // 定义剩余账户
-      const remainingAccounts = [
-        { pubkey: multisigPda, isSigner: false, isWritable: true },
-        { pubkey: vaultPda, isSigner: false, isWritable: false },
-      ]
+      txAccount = await program.account.transaction.fetch(txPda)
+      expect(txAccount.approvals).toHaveLength(0)
+      expect(txAccount.status).toEqual({ rejected: {} })
 
-      // 执行交易
-      await executeTxWithInstruction(changeThresholdIx, remainingAccounts)
-
-      // 验证阈值已修改
-      const msAccount = await program.account.multisig.fetch(multisigPda)
-      expect(msAccount.threshold).toBe(newThreshold)
+      // 被拒绝的提案不可再批准
+      await expect(
+        program.methods
+          .approve()
+          .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerC.publicKey })
+          .signers([ownerC])
+          .rpc(),
+      ).rejects.toThrow(/ProposalRejected/)
 
-      // 改回阈值 2 以便后续测试
-      await executeTxWithInstruction(
-        program.methods.changeThreshold(2).accounts({ multisig: multisigPda, vault: vaultPda }),
-        remainingAccounts,
-      )
+      // 被拒绝的提案可以关闭
+      await program.methods
+        .closeTransaction()
+        .accounts({
+          multisig: multisigPda,
+          transaction: txPda,
+          closer: ownerA.publicKey,
+          authorizedCloser: ownerA.publicKey,
+        })
+        .signers([ownerA])
+        .rpc()
+      await expect(program.account.transaction.fetch(txPda)).rejects.toThrow()
     })
 
-    // 测试用例：添加新所有者
-    it('应该成功添加一个新 owner', async () => {
-      const newOwner = Keypair.generate() // 生成新所有者密钥对
-      // 定义添加所有者指令
-      const addOwnerIx = program.methods
-        .addOwner(newOwner.publicKey)
-        .accounts({ multisig: multisigPda, vault: vaultPda })
+    it('应该通过 SOL 转账快捷指令发起提案并执行', async () => {
+      const recipient = Keypair.generate().publicKey
+      const lamports = LAMPORTS_PER_SOL / 100
+      const nextTxPda = async () => {
+        const msAccount = await program.account.multisig.fetch(multisigPda)
+        return PublicKey.findProgramAddressSync(
+          [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+          program.programId,
+        )[0]
+      }
+      const proposeAccounts = (txPda: PublicKey) => ({
+        multisig: multisigPda,
+        whitelist: whitelistPda,
+        vault: vaultPda,
+        transaction: txPda,
+        proposer: ownerA.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+
+      // 金额为 0 或收款地址为金库自身时拒绝
+      const txPda = await nextTxPda()
+      await expect(
+        program.methods
+          .proposeSolTransfer(recipient, new BN(0), null, true, 0)
+          .accounts(proposeAccounts(txPda))
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/InvalidAmount/)
+      await expect(
+        program.methods
+          .proposeSolTransfer(vaultPda, new BN(lamports), null, true, 0)
+          .accounts(proposeAccounts(txPda))
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/InvalidDestination/)
 
-      // 定义剩余账户
-      const remainingAccounts = [
-        { pubkey: multisigPda, isSigner: false, isWritable: true },
-        { pubkey: vaultPda, isSigner: false, isWritable: false },
-      ]
+      // 链上构造的转账指令由金库签名
+      await program.methods
+        .proposeSolTransfer(recipient, new BN(lamports), null, true, 0)
+        .accounts(proposeAccounts(txPda))
+        .signers([ownerA])
+        .rpc()
+      const txAccount = await program.account.transaction.fetch(txPda)
+      const { message } = txAccount
+      const [compiled] = message.instructions
+      expect(message.accountKeys[compiled.programIdIndex].equals(SystemProgram.programId)).toBe(true)
+      expect(message.accountKeys[compiled.accountIndexes[0]].equals(vaultPda)).toBe(true)
+      expect(compiled.accountIndexes[0]).toBeLessThan(message.numSigners)
 
-      // 执行交易
-      await executeTxWithInstruction(addOwnerIx, remainingAccounts)
+      // 照常批准并执行
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts([
+          { pubkey: vaultPda, isSigner: false, isWritable: true },
+          { pubkey: recipient, isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ])
+        .signers([ownerA])
+        .rpc()
+      expect(await provider.connection.getBalance(recipient)).toBe(lamports)
+    })
 
-      // 验证新所有者已添加
+    it('应该从索引金库发起转账，并拒绝与索引不符的金库', async () => {
+      const recipient = Keypair.generate().publicKey
+      const lamports = LAMPORTS_PER_SOL / 100
+      // 推导索引 1 的金库并注资
+      const [reserveVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from('vault'), multisigPda.toBuffer(), Buffer.from([1])],
+        program.programId,
+      )
+      await provider.sendAndConfirm(
+        new Web3Transaction().add(
+          SystemProgram.transfer({ fromPubkey: payer.publicKey, toPubkey: reserveVault, lamports: LAMPORTS_PER_SOL }),
+        ),
+      )
       const msAccount = await program.account.multisig.fetch(multisigPda)
-      expect(msAccount.owners.length).toBe(4) // 确认所有者数量
-      expect(msAccount.owners.map((o) => o.toBase58())).toContain(newOwner.publicKey.toBase58()) // 确认新所有者
-    })
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const proposeAccounts = (vault: PublicKey) => ({
+        multisig: multisigPda,
+        whitelist: whitelistPda,
+        vault,
+        transaction: txPda,
+        proposer: ownerA.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
 
-    // 测试用例：移除所有者
-    it('应该成功移除一个 owner', async () => {
-      const ownerToRemove = ownerC.publicKey // 要移除的所有者
-      // 定义移除所有者指令
-      const removeOwnerIx = program.methods
-        .removeOwner(ownerToRemove)
-        .accounts({ multisig: multisigPda, vault: vaultPda })
+      // 金库账户与索引不符时拒绝
+      await expect(
+        program.methods
+          .proposeSolTransfer(recipient, new BN(lamports), null, true, 1)
+          .accounts(proposeAccounts(vaultPda))
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/InvalidVault/)
 
-      // 定义剩余账户
-      const remainingAccounts = [
-        { pubkey: multisigPda, isSigner: false, isWritable: true },
-        { pubkey: vaultPda, isSigner: false, isWritable: false },
-      ]
+      await program.methods
+        .proposeSolTransfer(recipient, new BN(lamports), null, true, 1)
+        .accounts(proposeAccounts(reserveVault))
+        .signers([ownerA])
+        .rpc()
+      const txAccount = await program.account.transaction.fetch(txPda)
+      expect(txAccount.vaultIndex).toBe(1)
 
-      // 执行交易
-      await executeTxWithInstruction(removeOwnerIx, remainingAccounts)
+      // 执行时由索引 1 的金库签名
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts([
+          { pubkey: reserveVault, isSigner: false, isWritable: true },
+          { pubkey: recipient, isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ])
+        .signers([ownerA])
+        .rpc()
+      expect(await provider.connection.getBalance(recipient)).toBe(lamports)
+    })
 
-      // 验证所有者已移除
+    it('应该由临时签名者签名创建新账户', async () => {
       const msAccount = await program.account.multisig.fetch(multisigPda)
-      expect(msAccount.owners.length).toBe(3) // 确认所有者数量
-      expect(msAccount.owners.map((o) => o.toBase58())).not.toContain(ownerToRemove.toBase58()) // 确认已移除
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      // 临时签名者由交易地址和索引推导
+      const [ephemeralSigner] = PublicKey.findProgramAddressSync(
+        [Buffer.from('ephemeral'), txPda.toBuffer(), Buffer.from([0])],
+        program.programId,
+      )
+      const lamports = await provider.connection.getMinimumBalanceForRentExemption(0)
+      const ix = SystemProgram.createAccount({
+        fromPubkey: vaultPda,
+        newAccountPubkey: ephemeralSigner,
+        lamports,
+        space: 0,
+        programId: SystemProgram.programId,
+      })
+      const ixData = {
+        programId: ix.programId,
+        accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+        data: ix.data,
+      }
+      const proposeAccounts = {
+        multisig: multisigPda,
+        whitelist: whitelistPda,
+        vault: vaultPda,
+        transaction: txPda,
+        proposer: ownerA.publicKey,
+        systemProgram: SystemProgram.programId,
+      }
+
+      // 未声明临时签名者时，新账户不能作为 signer
+      await expect(
+        program.methods
+          .propose([ixData], null, true, 0, 0, { atomic: {} }, [])
+          .accounts(proposeAccounts)
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/SignerNotAllowed/)
+
+      await program.methods
+        .propose([ixData], null, true, 0, 1, { atomic: {} }, [])
+        .accounts(proposeAccounts)
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts([
+          { pubkey: vaultPda, isSigner: false, isWritable: true },
+          { pubkey: ephemeralSigner, isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ])
+        .signers([ownerA])
+        .rpc()
+      expect(await provider.connection.getBalance(ephemeralSigner)).toBe(lamports)
+    })
+
+    it('应该通过交易缓冲区分块上传并转换为提案', async () => {
+      const recipient = Keypair.generate().publicKey
+      const lamports = LAMPORTS_PER_SOL / 100
+      // 构造转账指令的紧凑消息并按 Borsh 序列化为缓冲区内容：
+      // 账户表依次为可写签名的金库、可写的收款地址和只读的系统程序
+      const transfer = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: recipient, lamports })
+      const content = program.coder.types.encode('transactionMessage', {
+        numSigners: 1,
+        numWritableSigners: 1,
+        numWritableNonSigners: 1,
+        accountKeys: [vaultPda, recipient, SystemProgram.programId],
+        instructions: [{ programIdIndex: 2, accountIndexes: Buffer.from([0, 1]), data: transfer.data }],
+        addressTableLookups: [],
+      })
+      const finalHash = Array.from(createHash('sha256').update(content).digest())
+
+      const bufferIndex = 0
+      const [transactionBuffer] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('transaction_buffer'),
+          multisigPda.toBuffer(),
+          ownerA.publicKey.toBuffer(),
+          Buffer.from([bufferIndex]),
+        ],
+        program.programId,
+      )
+      const half = Math.floor(content.length / 2)
+      await program.methods
+        .createTransactionBuffer(bufferIndex, 0, finalHash, content.length, content.subarray(0, half))
+        .accounts({ multisig: multisigPda, creator: ownerA.publicKey })
+        .signers([ownerA])
+        .rpc()
+
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const proposeAccounts = {
+        multisig: multisigPda,
+        whitelist: whitelistPda,
+        vault: vaultPda,
+        transactionBuffer,
+        transaction: txPda,
+        proposer: ownerA.publicKey,
+        systemProgram: SystemProgram.programId,
+      }
+
+      // 上传未完成时不能转换为提案
+      await expect(
+        program.methods
+          .proposeFromBuffer(null, true, 0, { atomic: {} }, [])
+          .accounts(proposeAccounts)
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/TransactionBufferIncomplete/)
+      // 超出声明长度的数据块被拒绝
+      await expect(
+        program.methods
+          .extendTransactionBuffer(Buffer.concat([content.subarray(half), Buffer.from([0])]))
+          .accounts({ multisig: multisigPda, transactionBuffer, creator: ownerA.publicKey })
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/TransactionBufferOverflow/)
+
+      await program.methods
+        .extendTransactionBuffer(content.subarray(half))
+        .accounts({ multisig: multisigPda, transactionBuffer, creator: ownerA.publicKey })
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .proposeFromBuffer(null, true, 0, { atomic: {} }, [])
+        .accounts(proposeAccounts)
+        .signers([ownerA])
+        .rpc()
+
+      // 缓冲区已关闭，交易账户按内容分配空间
+      expect(await provider.connection.getAccountInfo(transactionBuffer)).toBeNull()
+      const txInfo = await provider.connection.getAccountInfo(txPda)
+      expect(txInfo!.data.length).toBeLessThan(program.account.transaction.size)
+
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts([
+          { pubkey: vaultPda, isSigner: false, isWritable: true },
+          { pubkey: recipient, isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ])
+        .signers([ownerA])
+        .rpc()
+      expect(await provider.connection.getBalance(recipient)).toBe(lamports)
+    })
+
+    it('应该通过地址查找表引用账户并执行提案', async () => {
+      const recipient = Keypair.generate().publicKey
+      const lamports = LAMPORTS_PER_SOL / 100
+      // 创建地址查找表并写入收款地址
+      const slot = await provider.connection.getSlot('finalized')
+      const [createTableIx, lookupTable] = AddressLookupTableProgram.createLookupTable({
+        authority: payer.publicKey,
+        payer: payer.publicKey,
+        recentSlot: slot,
+      })
+      const extendTableIx = AddressLookupTableProgram.extendLookupTable({
+        lookupTable,
+        authority: payer.publicKey,
+        payer: payer.publicKey,
+        addresses: [recipient],
+      })
+      await provider.sendAndConfirm(new Web3Transaction().add(createTableIx, extendTableIx))
+      // 等待新写入的地址生效
+      const extendedSlot = await provider.connection.getSlot()
+      while ((await provider.connection.getSlot()) <= extendedSlot) {
+        await new Promise((resolve) => setTimeout(resolve, 100))
+      }
+
+      // 账户表只存放金库和系统程序，收款地址通过查找表的可写索引 0 引用（消息索引 2）
+      const transfer = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: recipient, lamports })
+      const content = program.coder.types.encode('transactionMessage', {
+        numSigners: 1,
+        numWritableSigners: 1,
+        numWritableNonSigners: 0,
+        accountKeys: [vaultPda, SystemProgram.programId],
+        instructions: [{ programIdIndex: 1, accountIndexes: Buffer.from([0, 2]), data: transfer.data }],
+        addressTableLookups: [
+          { accountKey: lookupTable, writableIndexes: Buffer.from([0]), readonlyIndexes: Buffer.from([]) },
+        ],
+      })
+      const finalHash = Array.from(createHash('sha256').update(content).digest())
+      const bufferIndex = 1
+      const [transactionBuffer] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('transaction_buffer'),
+          multisigPda.toBuffer(),
+          ownerA.publicKey.toBuffer(),
+          Buffer.from([bufferIndex]),
+        ],
+        program.programId,
+      )
+      await program.methods
+        .createTransactionBuffer(bufferIndex, 0, finalHash, content.length, content)
+        .accounts({ multisig: multisigPda, creator: ownerA.publicKey })
+        .signers([ownerA])
+        .rpc()
+
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const proposeAccounts = {
+        multisig: multisigPda,
+        whitelist: whitelistPda,
+        vault: vaultPda,
+        transactionBuffer,
+        transaction: txPda,
+        proposer: ownerA.publicKey,
+        systemProgram: SystemProgram.programId,
+      }
+      // 提案时需传入查找表，以便按实际账户校验
+      await expect(
+        program.methods
+          .proposeFromBuffer(null, true, 0, { atomic: {} }, [])
+          .accounts(proposeAccounts)
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/InvalidAddressLookupTable/)
+      await program.methods
+        .proposeFromBuffer(null, true, 0, { atomic: {} }, [])
+        .accounts(proposeAccounts)
+        .remainingAccounts([{ pubkey: lookupTable, isSigner: false, isWritable: false }])
+        .signers([ownerA])
+        .rpc()
+
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      // 执行时查找表排在剩余账户最前
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts([
+          { pubkey: lookupTable, isSigner: false, isWritable: false },
+          { pubkey: vaultPda, isSigner: false, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: recipient, isSigner: false, isWritable: true },
+        ])
+        .signers([ownerA])
+        .rpc()
+      expect(await provider.connection.getBalance(recipient)).toBe(lamports)
+    })
+
+    it('分步执行的提案应该逐条执行指令，并在全部完成后标记为已执行', async () => {
+      const recipients = [Keypair.generate().publicKey, Keypair.generate().publicKey]
+      const lamports = LAMPORTS_PER_SOL / 100
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const instructions = recipients.map((toPubkey) => {
+        const ix = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey, lamports })
+        return {
+          programId: ix.programId,
+          accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+          data: ix.data,
+        }
+      })
+      await program.methods
+        .propose(instructions, null, true, 0, 0, { stepwise: {} }, [])
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      const remainingAccounts = await executeAccounts(txPda)
+      const executeAccountsFor = { multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey }
+
+      // 分步提案不能一次性执行
+      await expect(
+        program.methods
+          .execute()
+          .accounts(executeAccountsFor)
+          .remainingAccounts(remainingAccounts)
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/StepwiseExecutionRequired/)
+
+      // 第一步只执行第一条指令，提案进入执行中状态
+      await program.methods
+        .executeStep()
+        .accounts(executeAccountsFor)
+        .remainingAccounts(remainingAccounts)
+        .signers([ownerA])
+        .rpc()
+      let txAccount = await program.account.transaction.fetch(txPda)
+      expect(txAccount.status).toEqual({ executing: {} })
+      expect(txAccount.nextIxIndex).toBe(1)
+      expect(await provider.connection.getBalance(recipients[0])).toBe(lamports)
+      expect(await provider.connection.getBalance(recipients[1])).toBe(0)
+
+      // 第二步执行剩余指令后提案标记为已执行
+      await program.methods
+        .executeStep()
+        .accounts(executeAccountsFor)
+        .remainingAccounts(remainingAccounts)
+        .signers([ownerA])
+        .rpc()
+      txAccount = await program.account.transaction.fetch(txPda)
+      expect(txAccount.status).toEqual({ executed: {} })
+      expect(await provider.connection.getBalance(recipients[1])).toBe(lamports)
+      await expect(
+        program.methods
+          .executeStep()
+          .accounts(executeAccountsFor)
+          .remainingAccounts(remainingAccounts)
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/AlreadyExecuted/)
+    })
+
+    it('执行后余额断言不成立时应该回滚整笔执行', async () => {
+      const recipient = Keypair.generate().publicKey
+      const lamports = LAMPORTS_PER_SOL / 100
+      const ix = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: recipient, lamports })
+      const ixData = {
+        programId: ix.programId,
+        accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+        data: ix.data,
+      }
+      const maxDecrease = (amount: number) => ({
+        maxLamportDecrease: { account: vaultPda, maxDecrease: new BN(amount) },
+      })
+      const proposeWith = async (assertions) => {
+        const msAccount = await program.account.multisig.fetch(multisigPda)
+        const [txPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+          program.programId,
+        )
+        await program.methods
+          .propose([ixData], null, true, 0, 0, { atomic: {} }, assertions)
+          .accounts({
+            multisig: multisigPda,
+            whitelist: whitelistPda,
+            vault: vaultPda,
+            transaction: txPda,
+            proposer: ownerA.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ownerA])
+          .rpc()
+        await program.methods
+          .approve()
+          .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+          .signers([ownerB])
+          .rpc()
+        return txPda
+      }
+      const execute = async (txPda: PublicKey) =>
+        program.methods
+          .execute()
+          .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+          .remainingAccounts(await executeAccounts(txPda))
+          .signers([ownerA])
+          .rpc()
+
+      // 断言的账户必须出现在提案指令中
+      await expect(
+        proposeWith([{ minTokenBalance: { tokenAccount: ownerC.publicKey, minAmount: new BN(1) } }]),
+      ).rejects.toThrow(/InvalidBalanceAssertion/)
+
+      // 金库减少量超过上限时执行失败，转账被回滚
+      const strictTx = await proposeWith([maxDecrease(lamports / 2)])
+      await expect(execute(strictTx)).rejects.toThrow(/BalanceAssertionFailed/)
+      expect(await provider.connection.getBalance(recipient)).toBe(0)
+      expect((await program.account.transaction.fetch(strictTx)).status).toEqual({ approved: {} })
+
+      // 减少量在上限内时正常执行
+      const allowedTx = await proposeWith([maxDecrease(lamports)])
+      await execute(allowedTx)
+      expect(await provider.connection.getBalance(recipient)).toBe(lamports)
+    })
+
+    it('应该按成员权重累计批准', async () => {
+      // 创建加权多签：ownerA 权重 2，ownerB、ownerC 权重 1，阈值 2
+      const localNonce = new BN(Date.now() + Math.floor(Math.random() * 1000000))
+      const { multisigPda: ms, vaultPda: vault, whitelistPda: wl } = findPdas(payer.publicKey, localNonce)
+      await program.methods
+        .createMultisig(
+          [...asMembers([ownerA.publicKey], PERMISSION_ALL, 2), ...asMembers([ownerB.publicKey, ownerC.publicKey])],
+          2,
+          localNonce,
+        )
+        .accountsPartial({ multisig: ms, vault, whitelist: wl, payer: payer.publicKey })
+        .rpc()
+
+      // 定义转账指令
+      const dummyInstruction = {
+        programId: SystemProgram.programId,
+        accounts: [
+          { pubkey: vault, isSigner: true, isWritable: true },
+          { pubkey: payer.publicKey, isSigner: false, isWritable: true },
+        ],
+        data: SystemProgram.transfer({ fromPubkey: vault, toPubkey: payer.publicKey, lamports: 1000 }).data,
+      }
+      const proposeWeighted = async (proposer: Keypair) => {
+        const msAccount = await program.account.multisig.fetch(ms)
+        const [txPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from('tx'), ms.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+          program.programId,
+        )
+        await program.methods
+          .propose([dummyInstruction], null, true, 0, 0, { atomic: {} }, [])
+          .accounts({
+            multisig: ms,
+            whitelist: wl,
+            vault,
+            transaction: txPda,
+            proposer: proposer.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([proposer])
+          .rpc()
+        return txPda
+      }
+
+      // ownerA 的单票权重即达到阈值
+      const heavyTx = await proposeWeighted(ownerA)
+      expect((await program.account.transaction.fetch(heavyTx)).status).toEqual({ approved: {} })
+
+      // ownerB 的单票权重不足，需要 ownerC 再批准
+      const lightTx = await proposeWeighted(ownerB)
+      expect((await program.account.transaction.fetch(lightTx)).status).toEqual({ active: {} })
+      await program.methods
+        .approve()
+        .accounts({ multisig: ms, transaction: lightTx, owner: ownerC.publicKey })
+        .signers([ownerC])
+        .rpc()
+      expect((await program.account.transaction.fetch(lightTx)).status).toEqual({ approved: {} })
+    })
+  })
+
+  // 测试套件：管理功能（通过提案、批准、执行流程）
+  describe('Management Functions', () => {
+    // 辅助函数：执行包含管理指令的交易
+    const executeTxWithInstruction = async (instructionPromise) => {
+      // 获取多签账户状态
+      const multisigAccount = await program.account.multisig.fetch(multisigPda)
+      const txId = multisigAccount.nextTxId
+      // 计算交易 PDA
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), txId.toBuffer('le', 8)],
+        program.programId,
+      )
+
+      // 获取指令数据
+      const ix = await instructionPromise.instruction()
+      const ixData = {
+        programId: program.programId,
+        accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+        data: ix.data,
+      }
+      // 提案并由 ownerA 自动批准
+      await program.methods
+        .propose([ixData], null, true, 0, 0, { atomic: {} }, [])
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+
+      // ownerB 批准以达到阈值
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+
+      // 执行交易
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts(await executeAccounts(txPda))
+        .signers([ownerA])
+        .rpc()
+    }
+
+    // 辅助函数：由 ownerA 提出包含多条指令的提案（不自动批准），返回交易 PDA
+    const proposeInstructions = async (ixs: TransactionInstruction[]) => {
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      await program.methods
+        .propose(
+          ixs.map((ix) => ({
+            programId: ix.programId,
+            accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+            data: ix.data,
+          })),
+          null,
+          false,
+          0,
+          0,
+          { atomic: {} },
+          [],
+        )
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+      return txPda
+    }
+
+    // 测试用例：修改阈值
+    it('应该成功修改阈值', async () => {
+      const newThreshold = 1 // 新阈值
+      // 定义修改阈值指令
+      const changeThresholdIx = program.methods
+        .changeThreshold(newThreshold)
+        .accounts({ multisig: multisigPda, vault: vaultPda })
+
+      // 执行交易
+      await executeTxWithInstruction(changeThresholdIx)
+
+      // 验证阈值已修改
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      expect(msAccount.threshold).toBe(newThreshold)
+
+      // 改回阈值 2 以便后续测试
+      await executeTxWithInstruction(
+        program.methods.changeThreshold(2).accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+    })
+
+    // 测试用例：添加新所有者
+    it('应该成功添加一个新 owner', async () => {
+      const newOwner = Keypair.generate() // 生成新所有者密钥对
+      // 定义添加所有者指令
+      const addOwnerIx = program.methods
+        .addOwner(newOwner.publicKey, PERMISSION_ALL, 1)
+        .accounts({ multisig: multisigPda, vault: vaultPda })
+
+      // 执行交易
+      await executeTxWithInstruction(addOwnerIx)
+
+      // 验证新所有者已添加
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      expect(msAccount.owners.length).toBe(4) // 确认所有者数量
+      expect(msAccount.owners.map((o) => o.key.toBase58())).toContain(newOwner.publicKey.toBase58()) // 确认新所有者
+    })
+
+    // 测试用例：移除所有者
+    it('应该成功移除一个 owner', async () => {
+      const ownerToRemove = ownerC.publicKey // 要移除的所有者
+      // 定义移除所有者指令
+      const removeOwnerIx = program.methods
+        .removeOwner(ownerToRemove)
+        .accounts({ multisig: multisigPda, vault: vaultPda })
+
+      // 执行交易
+      await executeTxWithInstruction(removeOwnerIx)
+
+      // 验证所有者已移除
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      expect(msAccount.owners.length).toBe(3) // 确认所有者数量
+      expect(msAccount.owners.map((o) => o.key.toBase58())).not.toContain(ownerToRemove.toBase58()) // 确认已移除
+    })
+
+    // 测试用例：owner 集合或阈值变更后，旧提案需要刷新才能继续批准
+    it('配置变更后应该拒绝批准过时的提案，刷新后可继续批准', async () => {
+      // 获取多签账户状态并计算交易 PDA
+      const multisigAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), multisigAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+
+      // 定义转账指令
+      const dummyInstruction = {
+        programId: SystemProgram.programId,
+        accounts: [
+          { pubkey: vaultPda, isSigner: true, isWritable: true },
+          { pubkey: payer.publicKey, isSigner: false, isWritable: true },
+        ],
+        data: SystemProgram.transfer({
+          fromPubkey: vaultPda,
+          toPubkey: payer.publicKey,
+          lamports: 1000000,
+        }).data,
+      }
+
+      // ownerA 提案并自动批准
+      await program.methods
+        .propose([dummyInstruction], null, true, 0, 0, { atomic: {} }, [])
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+
+      // 通过管理流程修改阈值，配置代数递增
+      await executeTxWithInstruction(
+        program.methods.changeThreshold(2).accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+
+      // 期望批准过时提案失败
+      await expect(
+        program.methods
+          .approve()
+          .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+          .signers([ownerB])
+          .rpc(),
+      ).rejects.toThrow(/StaleProposal/)
+
+      // 提案者刷新提案
+      await program.methods
+        .refreshProposal()
+        .accounts({ multisig: multisigPda, transaction: txPda, proposer: ownerA.publicKey })
+        .signers([ownerA])
+        .rpc()
+
+      // 验证旧批准已清空且配置代数已同步
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const txAccount = await program.account.transaction.fetch(txPda)
+      expect(txAccount.approvals).toHaveLength(0)
+      expect(txAccount.configGeneration).toBe(msAccount.configGeneration)
+
+      // 刷新后可以继续批准
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+    })
+
+    // 测试用例：指定执行者策略后，只有列表中的账户可以执行
+    it('应该按执行者策略限制执行者', async () => {
+      // 指定 ownerA 为唯一执行者
+      await executeTxWithInstruction(
+        program.methods
+          .setExecutorPolicy({ designated: {} }, [ownerA.publicKey])
+          .accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      expect(msAccount.executorPolicy).toEqual({ designated: {} })
+      expect(msAccount.executors.map((e) => e.toBase58())).toEqual([ownerA.publicKey.toBase58()])
+
+      // 提案恢复为成员执行策略，并由 ownerB 尝试执行
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const ix = await program.methods
+        .setExecutorPolicy({ ownersOnly: {} }, [])
+        .accounts({ multisig: multisigPda, vault: vaultPda })
+        .instruction()
+      await program.methods
+        .propose(
+          [
+            {
+              programId: program.programId,
+              accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+              data: ix.data,
+            },
+          ],
+          null,
+          true,
+          0,
+          0,
+          { atomic: {} },
+          [],
+        )
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      await expect(
+        program.methods
+          .execute()
+          .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerB.publicKey })
+          .remainingAccounts(await executeAccounts(txPda))
+          .signers([ownerB])
+          .rpc(),
+      ).rejects.toThrow(/ExecutorNotAllowed/)
+
+      // 指定执行者 ownerA 可以执行
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts(await executeAccounts(txPda))
+        .signers([ownerA])
+        .rpc()
+      const msAccountAfter = await program.account.multisig.fetch(multisigPda)
+      expect(msAccountAfter.executorPolicy).toEqual({ ownersOnly: {} })
+    })
+
+    // 测试用例：启用时间锁后，达到阈值也需等待冷却期才能执行
+    it('启用时间锁后应该在冷却期结束前拒绝执行', async () => {
+      // 设置 2 秒时间锁
+      await executeTxWithInstruction(
+        program.methods.changeTimeLock(2).accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      expect(msAccount.timeLockSeconds).toBe(2)
+
+      // 关闭时间锁的提案在冷却期内执行应失败
+      await expect(
+        executeTxWithInstruction(
+          program.methods.changeTimeLock(0).accounts({ multisig: multisigPda, vault: vaultPda }),
+        ),
+      ).rejects.toThrow(/TimeLockNotElapsed/)
+
+      // 等待冷却期结束后执行刚才达到阈值的提案
+      await new Promise((resolve) => setTimeout(resolve, 3000))
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      await program.methods
+        .execute()
+        .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+        .remainingAccounts(await executeAccounts(txPda))
+        .signers([ownerA])
+        .rpc()
+
+      // 验证时间锁已关闭
+      const msAccountAfter = await program.account.multisig.fetch(multisigPda)
+      expect(msAccountAfter.timeLockSeconds).toBe(0)
+    }, 15000)
+
+    it('配置指令前缀后应该拒绝不匹配前缀的提案', async () => {
+      // 仅允许 System 程序的 Transfer 指令（u32 小端序标签 2）
+      const transferTag = Buffer.from([2, 0, 0, 0])
+      await executeTxWithInstruction(
+        program.methods
+          .addAllowedPrefix(SystemProgram.programId, transferTag)
+          .accounts({ multisig: multisigPda, whitelist: whitelistPda, vault: vaultPda }),
+      )
+
+      const proposeSystemIx = async (ix: TransactionInstruction) => {
+        const msAccount = await program.account.multisig.fetch(multisigPda)
+        const [txPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+          program.programId,
+        )
+        await program.methods
+          .propose(
+            [
+              {
+                programId: ix.programId,
+                accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+                data: ix.data,
+              },
+            ],
+            null,
+            false,
+            0,
+            0,
+            { atomic: {} },
+            [],
+          )
+          .accounts({
+            multisig: multisigPda,
+            whitelist: whitelistPda,
+            vault: vaultPda,
+            transaction: txPda,
+            proposer: ownerA.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([ownerA])
+          .rpc()
+      }
+
+      // Assign 指令不匹配前缀，提案被拒绝
+      await expect(
+        proposeSystemIx(SystemProgram.assign({ accountPubkey: vaultPda, programId: program.programId })),
+      ).rejects.toThrow(/InstructionNotAllowed/)
+      // Transfer 指令仍可提案
+      await proposeSystemIx(SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: payer.publicKey, lamports: 1000 }))
+    })
+
+    it('调用高风险等级程序的提案应该要求更高的阈值', async () => {
+      const riskyProgram = Keypair.generate().publicKey // 模拟质押/金库类程序
+      // 将程序以风险等级阈值 3 加入白名单
+      await executeTxWithInstruction(
+        program.methods
+          .addToWhitelist(riskyProgram, 3)
+          .accounts({ multisig: multisigPda, whitelist: whitelistPda, vault: vaultPda }),
+      )
+      const whitelistAccount = await program.account.programWhitelist.fetch(whitelistPda)
+      const entry = whitelistAccount.programs.find((e) => e.programId.equals(riskyProgram))
+      expect(entry?.requiredApprovals).toBe(3)
+
+      // 调用该程序的提案获得两票后仍处于投票中
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      await program.methods
+        .propose(
+          [{ programId: riskyProgram, accounts: [], data: Buffer.from([]) }],
+          null,
+          true,
+          0,
+          0,
+          { atomic: {} },
+          [],
+        )
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      expect((await program.account.transaction.fetch(txPda)).status).toEqual({ active: {} })
+      await expect(
+        program.methods
+          .execute()
+          .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/NotEnoughApprovals/)
+
+      // 将该程序移出白名单后，调用它的未执行提案随之失效
+      await executeTxWithInstruction(
+        program.methods
+          .removeFromWhitelist(riskyProgram)
+          .accounts({ multisig: multisigPda, whitelist: whitelistPda, vault: vaultPda }),
+      )
+      const txAccount = await program.account.transaction.fetch(txPda)
+      const msAccountAfter = await program.account.multisig.fetch(multisigPda)
+      expect(txAccount.configGeneration).not.toBe(msAccountAfter.configGeneration)
+      await expect(
+        program.methods
+          .approve()
+          .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+          .signers([ownerB])
+          .rpc(),
+      ).rejects.toThrow(/StaleProposal/)
+
+      // 刷新会按当前白名单重新验证指令，已移出白名单的程序不能借刷新恢复提案
+      await expect(
+        program.methods
+          .refreshProposal()
+          .accounts({ multisig: multisigPda, transaction: txPda, proposer: ownerA.publicKey })
+          .signers([ownerA])
+          .rpc(),
+      ).rejects.toThrow(/ProgramNotAllowed/)
+    })
+
+    it('分步执行中配置变更后应该停止剩余步骤，提议者可以取消并关闭提案', async () => {
+      const recipients = [Keypair.generate().publicKey, Keypair.generate().publicKey]
+      const lamports = LAMPORTS_PER_SOL / 100
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const instructions = recipients.map((toPubkey) => {
+        const ix = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey, lamports })
+        return {
+          programId: ix.programId,
+          accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+          data: ix.data,
+        }
+      })
+      await program.methods
+        .propose(instructions, null, true, 0, 0, { stepwise: {} }, [])
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      const remainingAccounts = await executeAccounts(txPda)
+      const executeStep = () =>
+        program.methods
+          .executeStep()
+          .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+          .remainingAccounts(remainingAccounts)
+          .signers([ownerA])
+          .rpc()
+      await executeStep()
+
+      // 执行第一步后由其他提案变更配置（启用地址簿会递增配置代数），剩余步骤不能继续
+      const [addressBookPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('address_book'), multisigPda.toBuffer()],
+        program.programId,
+      )
+      const manageAccounts = { multisig: multisigPda, addressBook: addressBookPda, vault: vaultPda }
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(true).accounts(manageAccounts))
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(false).accounts(manageAccounts))
+      await expect(executeStep()).rejects.toThrow(/StaleProposal/)
+      expect(await provider.connection.getBalance(recipients[1])).toBe(0)
+
+      // 提议者取消执行中的提案以中止剩余步骤，之后可以关闭交易账户
+      await program.methods
+        .cancelProposal()
+        .accounts({ multisig: multisigPda, transaction: txPda, proposer: ownerA.publicKey })
+        .signers([ownerA])
+        .rpc()
+      expect((await program.account.transaction.fetch(txPda)).status).toEqual({ cancelled: {} })
+      await program.methods
+        .closeTransaction()
+        .accounts({
+          multisig: multisigPda,
+          transaction: txPda,
+          closer: ownerA.publicKey,
+          authorizedCloser: ownerA.publicKey,
+        })
+        .signers([ownerA])
+        .rpc()
+      await expect(program.account.transaction.fetch(txPda)).rejects.toThrow()
+    })
+
+    it('启用地址簿后应该拒绝向地址簿外地址写入的提案', async () => {
+      const [addressBookPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('address_book'), multisigPda.toBuffer()],
+        program.programId,
+      )
+      const manageAccounts = { multisig: multisigPda, addressBook: addressBookPda, vault: vaultPda }
+      const payee = Keypair.generate().publicKey // 已批准的收款地址
+      const stranger = Keypair.generate().publicKey // 未登记的地址
+
+      // 登记收款地址并启用地址簿
+      await executeTxWithInstruction(program.methods.addAddressBookEntry(payee).accounts(manageAccounts))
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(true).accounts(manageAccounts))
+      const addressBook = await program.account.addressBook.fetch(addressBookPda)
+      expect(addressBook.enabled).toBe(true)
+      expect(addressBook.addresses.map((a) => a.toBase58())).toEqual([payee.toBase58()])
+
+      const proposeTransferTo = (to: PublicKey) =>
+        proposeInstructions([SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: to, lamports: 1000 })])
+
+      // 向未登记地址转账的提案被拒绝，向已登记地址转账的提案可以发起
+      await expect(proposeTransferTo(stranger)).rejects.toThrow(/DestinationNotAllowed/)
+      await proposeTransferTo(payee)
+
+      // 启用地址簿时，支出限额必须列出目标地址且均在地址簿中
+      const limitMint = Keypair.generate().publicKey
+      const [limitPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('spending_limit'), multisigPda.toBuffer(), limitMint.toBuffer()],
+        program.programId,
+      )
+      const addLimit = (destinations: PublicKey[]) =>
+        program.methods
+          .addSpendingLimit(limitMint, new BN(1_000), { day: {} }, [ownerA.publicKey], destinations)
+          .accounts({ multisig: multisigPda, spendingLimit: limitPda, vault: vaultPda })
+      await expect(executeTxWithInstruction(addLimit([]))).rejects.toThrow(/DestinationNotAllowed/)
+      await expect(executeTxWithInstruction(addLimit([stranger]))).rejects.toThrow(/DestinationNotAllowed/)
+      // 支出限额 PDA 只对推导出它的指令豁免，同一提案中向其转账的指令仍受地址簿限制
+      const transferToLimit = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: limitPda, lamports: 1000 })
+      await expect(proposeInstructions([await addLimit([payee]).instruction(), transferToLimit])).rejects.toThrow(
+        /DestinationNotAllowed/,
+      )
+      await executeTxWithInstruction(addLimit([payee]))
+      const limitAccount = await program.account.spendingLimit.fetch(limitPda)
+      expect(limitAccount.destinations.map((d) => d.toBase58())).toEqual([payee.toBase58()])
+
+      // 停用地址簿，恢复不限制目标地址
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(false).accounts(manageAccounts))
+      await proposeTransferTo(stranger)
+    })
+
+    it('授权成员应该可以在支出限额内直接从金库转出 SOL', async () => {
+      const [spendingLimitPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('spending_limit'), multisigPda.toBuffer(), PublicKey.default.toBuffer()],
+        program.programId,
+      )
+      const payee = Keypair.generate().publicKey // 允许的收款地址
+      const limitAmount = 1_000_000 // 每天额度 0.001 SOL
+
+      // 通过提案为 ownerA 创建每日 SOL 支出限额
+      await executeTxWithInstruction(
+        program.methods
+          .addSpendingLimit(PublicKey.default, new BN(limitAmount), { day: {} }, [ownerA.publicKey], [payee])
+          .accounts({ multisig: multisigPda, spendingLimit: spendingLimitPda, vault: vaultPda }),
+      )
+      const limitAccount = await program.account.spendingLimit.fetch(spendingLimitPda)
+      expect(limitAccount.remainingAmount.toNumber()).toBe(limitAmount)
+
+      const useLimit = (member: Keypair, destination: PublicKey, amount: number) =>
+        program.methods
+          .spendingLimitUse(new BN(amount))
+          .accountsPartial({
+            multisig: multisigPda,
+            spendingLimit: spendingLimitPda,
+            member: member.publicKey,
+            vault: vaultPda,
+            destination,
+            systemProgram: SystemProgram.programId,
+            mint: null,
+            vaultTokenAccount: null,
+            destinationTokenAccount: null,
+            tokenProgram: null,
+          })
+          .signers([member])
+          .rpc()
+
+      // ownerA 单独转出，无需提案
+      await useLimit(ownerA, payee, 900_000)
+      expect(await provider.connection.getBalance(payee)).toBe(900_000)
+      const afterUse = await program.account.spendingLimit.fetch(spendingLimitPda)
+      expect(afterUse.remainingAmount.toNumber()).toBe(100_000)
+
+      // 超出剩余额度、未授权成员或未允许的目标地址均被拒绝
+      await expect(useLimit(ownerA, payee, 200_000)).rejects.toThrow(/SpendingLimitExceeded/)
+      await expect(useLimit(ownerB, payee, 1_000)).rejects.toThrow(/SpendingLimitMemberNotAllowed/)
+      await expect(useLimit(ownerA, Keypair.generate().publicKey, 1_000)).rejects.toThrow(
+        /SpendingLimitDestinationNotAllowed/,
+      )
+
+      // 额度在启用地址簿前创建，启用后使用时目标地址仍需在地址簿中
+      const [addressBookPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('address_book'), multisigPda.toBuffer()],
+        program.programId,
+      )
+      const manageAccounts = { multisig: multisigPda, addressBook: addressBookPda, vault: vaultPda }
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(true).accounts(manageAccounts))
+      await expect(useLimit(ownerA, payee, 1_000)).rejects.toThrow(/DestinationNotAllowed/)
+      await executeTxWithInstruction(program.methods.addAddressBookEntry(payee).accounts(manageAccounts))
+      await useLimit(ownerA, payee, 1_000)
+      expect(await provider.connection.getBalance(payee)).toBe(901_000)
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(false).accounts(manageAccounts))
+    })
+
+    it('应该通过提案创建金库代币账户、转出代币并关闭账户', async () => {
+      // 创建测试代币并推导金库的关联代币账户
+      const mint = await createMint(provider.connection, payer.payer, payer.publicKey, null, 6)
+      const vaultAta = getAssociatedTokenAddressSync(mint, vaultPda, true)
+      const tokenAccounts = {
+        multisig: multisigPda,
+        vault: vaultPda,
+        mint,
+        vaultTokenAccount: vaultAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      }
+
+      // 由金库支付租金创建关联代币账户
+      await executeTxWithInstruction(program.methods.createVaultTokenAccount(0).accounts(tokenAccounts))
+      await mintTo(provider.connection, payer.payer, mint, vaultAta, payer.payer, 1_000)
+
+      // 登记收款人并启用地址簿
+      const [addressBookPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('address_book'), multisigPda.toBuffer()],
+        program.programId,
+      )
+      const manageAccounts = { multisig: multisigPda, addressBook: addressBookPda, vault: vaultPda }
+      const payee = Keypair.generate().publicKey
+      const payeeAta = await createAssociatedTokenAccount(provider.connection, payer.payer, mint, payee)
+      await executeTxWithInstruction(program.methods.addAddressBookEntry(payee).accounts(manageAccounts))
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(true).accounts(manageAccounts))
+      const transferTo = (destinationTokenAccount: PublicKey) =>
+        program.methods
+          .vaultTokenTransfer(0, new BN(1_000), null)
+          .accounts({ ...tokenAccounts, destinationTokenAccount })
+
+      // 地址簿外的目标代币账户无法通过金库代币转账绕过地址簿
+      const stranger = Keypair.generate().publicKey
+      const strangerAta = await createAssociatedTokenAccount(provider.connection, payer.payer, mint, stranger)
+      await expect(executeTxWithInstruction(transferTo(strangerAta))).rejects.toThrow(/DestinationNotAllowed/)
+      expect((await getAccount(provider.connection, strangerAta)).amount).toBe(BigInt(0))
+
+      // 金库代币指令的豁免只对其自身有效，不能为同一提案中写入同一地址的其他指令放行
+      const offBook = Keypair.generate().publicKey
+      const solToOffBook = SystemProgram.transfer({ fromPubkey: vaultPda, toPubkey: offBook, lamports: 1000 })
+      await expect(proposeInstructions([solToOffBook, await transferTo(offBook).instruction()])).rejects.toThrow(
+        /DestinationNotAllowed/,
+      )
+      const closeOffBook = await program.methods
+        .closeVaultTokenAccount(0)
+        .accounts({
+          multisig: multisigPda,
+          vault: vaultPda,
+          vaultTokenAccount: offBook,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction()
+      await expect(proposeInstructions([solToOffBook, closeOffBook])).rejects.toThrow(/DestinationNotAllowed/)
+
+      // 启用地址簿时，代币转账快捷提案的源账户（金库的关联代币账户）无需登记，目标代币账户仍需登记
+      await executeTxWithInstruction(
+        program.methods
+          .addToWhitelist(TOKEN_PROGRAM_ID, 0)
+          .accounts({ multisig: multisigPda, whitelist: whitelistPda, vault: vaultPda }),
+      )
+      const proposeTokenTransferTo = async (to: PublicKey) => {
+        const msAccount = await program.account.multisig.fetch(multisigPda)
+        const [txPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+          program.programId,
+        )
+        await program.methods
+          .proposeTokenTransfer(mint, to, new BN(1), null, false, 0)
+          .accounts({
+            multisig: multisigPda,
+            whitelist: whitelistPda,
+            vault: vaultPda,
+            transaction: txPda,
+            proposer: ownerA.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([{ pubkey: mint, isSigner: false, isWritable: false }])
+          .signers([ownerA])
+          .rpc()
+      }
+      await expect(proposeTokenTransferTo(stranger)).rejects.toThrow(/DestinationNotAllowed/)
+      await executeTxWithInstruction(program.methods.addAddressBookEntry(payeeAta).accounts(manageAccounts))
+      await proposeTokenTransferTo(payee)
+
+      // 所有者在地址簿中时，将全部余额转给收款人
+      await executeTxWithInstruction(transferTo(payeeAta))
+      expect((await getAccount(provider.connection, payeeAta)).amount).toBe(BigInt(1_000))
+      await executeTxWithInstruction(program.methods.setAddressBookEnabled(false).accounts(manageAccounts))
+
+      // 余额为 0 后关闭金库代币账户，租金返还金库；金库索引须与传入的金库一致
+      const closeAccounts = {
+        multisig: multisigPda,
+        vault: vaultPda,
+        vaultTokenAccount: vaultAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      }
+      await expect(
+        executeTxWithInstruction(program.methods.closeVaultTokenAccount(1).accounts(closeAccounts)),
+      ).rejects.toThrow(/InvalidVault/)
+      await executeTxWithInstruction(program.methods.closeVaultTokenAccount(0).accounts(closeAccounts))
+      expect(await provider.connection.getAccountInfo(vaultAta)).toBeNull()
+    })
+
+    it('设置 guard 程序后执行需经过其钩子，清除 guard 的提案不受其影响', async () => {
+      // 设置 guard 程序
+      await executeTxWithInstruction(
+        program.methods.setGuardProgram(MEMO_PROGRAM_ID).accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+      expect((await program.account.multisig.fetch(multisigPda)).guardProgram.equals(MEMO_PROGRAM_ID)).toBe(true)
+
+      // 提出一笔普通转账并达到阈值
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      const [txPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('tx'), multisigPda.toBuffer(), msAccount.nextTxId.toBuffer('le', 8)],
+        program.programId,
+      )
+      const ix = SystemProgram.transfer({
+        fromPubkey: vaultPda,
+        toPubkey: Keypair.generate().publicKey,
+        lamports: LAMPORTS_PER_SOL / 100,
+      })
+      await program.methods
+        .propose(
+          [
+            {
+              programId: ix.programId,
+              accounts: ix.keys.map((k) => ({ pubkey: k.pubkey, isSigner: k.isSigner, isWritable: k.isWritable })),
+              data: ix.data,
+            },
+          ],
+          null,
+          true,
+          0,
+          0,
+          { atomic: {} },
+          [],
+        )
+        .accounts({
+          multisig: multisigPda,
+          whitelist: whitelistPda,
+          vault: vaultPda,
+          transaction: txPda,
+          proposer: ownerA.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([ownerA])
+        .rpc()
+      await program.methods
+        .approve()
+        .accounts({ multisig: multisigPda, transaction: txPda, owner: ownerB.publicKey })
+        .signers([ownerB])
+        .rpc()
+      const execute = async (guardAccounts: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[]) =>
+        program.methods
+          .execute()
+          .accounts({ multisig: multisigPda, transaction: txPda, executor: ownerA.publicKey })
+          .remainingAccounts([...(await executeAccounts(txPda)), ...guardAccounts])
+          .signers([ownerA])
+          .rpc()
+
+      // 未在提案账户之后传入 guard 程序时拒绝执行
+      await expect(execute([])).rejects.toThrow(/InvalidGuardProgram/)
+      // guard 钩子报错时整笔执行回滚
+      await expect(execute([{ pubkey: MEMO_PROGRAM_ID, isSigner: false, isWritable: false }])).rejects.toThrow()
+      expect((await program.account.transaction.fetch(txPda)).status).toEqual({ approved: {} })
+
+      // 清除 guard 的提案不经过 guard 钩子
+      await executeTxWithInstruction(
+        program.methods.setGuardProgram(null).accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+      expect((await program.account.multisig.fetch(multisigPda)).guardProgram).toBeNull()
+      await execute([])
+      expect((await program.account.transaction.fetch(txPda)).status).toEqual({ executed: {} })
+    })
+
+    it('配置变更提案应该要求达到配置变更阈值', async () => {
+      // 当前配置变更阈值为 2，ownerA 与 ownerB 的批准足以将其提高到 3
+      await executeTxWithInstruction(
+        program.methods.changeConfigThreshold(3).accounts({ multisig: multisigPda, vault: vaultPda }),
+      )
+      const msAccount = await program.account.multisig.fetch(multisigPda)
+      expect(msAccount.threshold).toBe(1)
+      expect(msAccount.configThreshold).toBe(3)
+
+      // 之后两票已不足以执行配置变更
+      await expect(
+        executeTxWithInstruction(
+          program.methods.changeConfigThreshold(2).accounts({ multisig: multisigPda, vault: vaultPda }),
+        ),
+      ).rejects.toThrow(/NotEnoughApprovals/)
     })
   })
 })
@@ -1,11 +1,11 @@
 'use client' // 声明此文件为客户端组件
 
 // 导入 Anchor 相关工具函数，用于获取多签程序实例和程序 ID
-import { getMultisigProgram, getMultisigProgramId } from '@project/anchor'
+import { getMultisigProgram, getMultisigProgramId, PERMISSION_ALL } from '@project/anchor'
 // 导入 Solana 钱包适配器 Hook，用于获取区块链连接
 import { useConnection } from '@solana/wallet-adapter-react'
 // 导入 Solana Web3.js 的核心类，用于处理公钥、系统程序、交易等
-import { Cluster, PublicKey, SystemProgram, LAMPORTS_PER_SOL, Transaction } from '@solana/web3.js'
+import { Cluster, Connection, PublicKey, SystemProgram, LAMPORTS_PER_SOL, Transaction } from '@solana/web3.js'
 // 导入 React Query 的 Hook，用于管理异步数据查询和变更
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
 // 导入 React 的 useMemo Hook，用于缓存计算结果
@@ -19,10 +19,61 @@ import { useTransactionToast } from '../use-transaction-toast'
 // 导入 Sonner 库，用于显示成功/错误提示
 import { toast } from 'sonner'
 // 导入 Anchor 的核心类，BN 用于大整数，Program 用于程序交互
-import { BN, Program } from '@coral-xyz/anchor'
+import { BN, IdlTypes, Program } from '@coral-xyz/anchor'
 // 导入多签程序的类型定义（IDL 生成）
 import { Multisig } from '@project/anchor'
 
+// 定义紧凑消息和完整指令数据的类型，基于 IDL
+type TransactionMessage = IdlTypes<Multisig>['transactionMessage']
+type InstructionData = IdlTypes<Multisig>['instructionData']
+
+// 定义从地址查找表解析出的账户
+type LoadedAddresses = { writable: PublicKey[]; readonly: PublicKey[] }
+
+// 判断紧凑消息中指定索引的账户是否可写；超出账户表的索引依次指向查找表中的可写、只读账户
+export function isWritableIndex(message: TransactionMessage, index: number) {
+  const numStaticKeys = message.accountKeys.length
+  if (index >= numStaticKeys) {
+    const numLoadedWritable = message.addressTableLookups.reduce((n, lookup) => n + lookup.writableIndexes.length, 0)
+    return index - numStaticKeys < numLoadedWritable
+  }
+  return (
+    index < message.numWritableSigners ||
+    (index >= message.numSigners && index < message.numSigners + message.numWritableNonSigners)
+  )
+}
+
+// 读取紧凑消息引用的地址查找表，先依次取各表的可写账户，再依次取各表的只读账户
+export async function loadAddresses(connection: Connection, message: TransactionMessage): Promise<LoadedAddresses> {
+  const tables = await Promise.all(
+    message.addressTableLookups.map((lookup) => connection.getAddressLookupTable(lookup.accountKey)),
+  )
+  const resolve = (indexes: 'writableIndexes' | 'readonlyIndexes') =>
+    message.addressTableLookups.flatMap((lookup, i) => {
+      const table = tables[i].value
+      if (!table) throw new Error(`地址查找表不存在: ${lookup.accountKey.toBase58()}`)
+      return Array.from(lookup[indexes]).map((index) => table.state.addresses[index])
+    })
+  return { writable: resolve('writableIndexes'), readonly: resolve('readonlyIndexes') }
+}
+
+// 将紧凑消息还原为完整的指令列表，用于解析和展示
+export function decompileMessage(
+  message: TransactionMessage,
+  loaded: LoadedAddresses = { writable: [], readonly: [] },
+): InstructionData[] {
+  const keys = [...message.accountKeys, ...loaded.writable, ...loaded.readonly]
+  return message.instructions.map((ix) => ({
+    programId: keys[ix.programIdIndex],
+    accounts: Array.from(ix.accountIndexes).map((index) => ({
+      pubkey: keys[index],
+      isSigner: index < message.numSigners,
+      isWritable: isWritableIndex(message, index),
+    })),
+    data: ix.data,
+  }))
+}
+
 // Hook：获取多签程序实例和账户数据，提供创建多签钱包的功能
 export function useMultisigProgram() {
   // 获取当前集群（主网、测试网、开发网）
@@ -50,7 +101,7 @@ export function useMultisigProgram() {
       const allMultisigAccounts = await program.account.multisig.all()
       // 过滤包含当前钱包的账户
       return allMultisigAccounts.filter((account) =>
-        account.account.owners.some((owner) => owner.equals(wallet.publicKey!)),
+        account.account.owners.some((owner) => owner.key.equals(wallet.publicKey!)),
       )
     },
     // 仅在钱包公钥存在时启用查询
@@ -94,7 +145,11 @@ export function useMultisigProgram() {
 
       // 创建多签账户交易
       const createTxSignature = await program.methods
-        .createMultisig(owners, threshold, nonce) // 调用链上 createMultisig 方法
+        .createMultisig(
+          owners.map((key) => ({ key, permissions: PERMISSION_ALL, weight: 1 })), // 所有者默认拥有全部权限和相同权重
+          threshold,
+          nonce,
+        ) // 调用链上 createMultisig 方法
         .accounts({
           multisig: multisigPda, // 多签账户 PDA
           vault: vaultPda, // 金库账户 PDA
@@ -282,7 +337,7 @@ export function useMultisigProgramAccount({ account: multisigAccount }: { accoun
 
       // 调用链上 propose 方法
       return program.methods
-        .propose(instructions, null, autoApprove)
+        .propose(instructions, null, autoApprove, 0, 0, { atomic: {} }, [])
         .accounts({
           multisig: multisigAccount, // 多签账户
           whitelist: whitelistPda, // 白名单账户
@@ -406,23 +461,35 @@ export function useTransactionProgramAccount({ transactionAccount }: { transacti
       const txData = accountQuery.data
       if (!txData || !multisigAddress) throw new Error('交易数据未加载')
 
-      // 构造 remainingAccounts，从交易数据中提取指令账户
-      const remainingAccounts = txData.instructions.flatMap((instruction) =>
-        instruction.accounts.map((account) => ({
-          pubkey: account.pubkey, // 账户公钥
+      // 构造 remainingAccounts：地址查找表排在最前，之后是去重后的账户表（包含各指令的程序 ID）
+      // 以及从查找表解析出的账户
+      const { message } = txData
+      const loaded = await loadAddresses(provider.connection, message)
+      const remainingAccounts = [
+        ...message.addressTableLookups.map((lookup) => ({
+          pubkey: lookup.accountKey, // 地址查找表
+          isSigner: false,
+          isWritable: false,
+        })),
+        ...[...message.accountKeys, ...loaded.writable, ...loaded.readonly].map((pubkey, index) => ({
+          pubkey, // 账户公钥
           isSigner: false, // 客户端不提供签名，链上通过 invoke_signed 处理
-          isWritable: account.isWritable, // 保留可写属性
+          isWritable: isWritableIndex(message, index), // 保留可写属性
         })),
-      )
-
-      // 将指令的目标程序 ID 添加到 remainingAccounts
-      txData.instructions.forEach((instruction) => {
-        remainingAccounts.push({
-          pubkey: instruction.programId, // 程序 ID
-          isSigner: false, // 程序不签名
-          isWritable: false, // 程序不可写
-        })
-      })
+      ]
+
+      // 设置了 guard 程序时在最后传入 guard 程序；仅设置 guard 的提案不经过 guard 钩子
+      const guardProgram = multisigAccountQuery.data?.guardProgram
+      const setGuardIx = program.idl.instructions.find((ix) => ix.name === 'set_guard_program')
+      const [onlyIx] = message.instructions
+      const isGuardUpdate =
+        message.instructions.length === 1 &&
+        message.accountKeys[onlyIx.programIdIndex].equals(program.programId) &&
+        !!setGuardIx &&
+        Buffer.from(onlyIx.data).subarray(0, 8).equals(Buffer.from(setGuardIx.discriminator))
+      if (guardProgram && !isGuardUpdate) {
+        remainingAccounts.push({ pubkey: guardProgram, isSigner: false, isWritable: false })
+      }
 
       // 打印 remainingAccounts 用于调试
       console.log(
@@ -430,12 +497,13 @@ export function useTransactionProgramAccount({ transactionAccount }: { transacti
         remainingAccounts.map((a) => ({ ...a, pubkey: a.pubkey.toBase58() })),
       )
 
-      // 调用链上 execute 方法
-      return program.methods
-        .execute()
+      // 调用链上 execute 方法，分步执行的提案每次调用 executeStep 执行下一条指令
+      const method = 'stepwise' in txData.executionMode ? program.methods.executeStep() : program.methods.execute()
+      return method
         .accounts({
           multisig: multisigAddress, // 多签账户
           transaction: transactionAccount, // 交易账户
+          executor: provider.wallet.publicKey, // 执行者
         })
         .remainingAccounts(remainingAccounts) // 提供动态账户列表
         .rpc() // 发送交易
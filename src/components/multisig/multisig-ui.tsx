@@ -8,7 +8,12 @@ import { useState } from 'react'
 // 导入自定义组件，用于生成指向区块链浏览器的链接
 import { ExplorerLink } from '../cluster/cluster-ui'
 // 导入自定义 Hook，提供对多签程序、账户和交易的访问
-import { useMultisigProgram, useMultisigProgramAccount, useTransactionProgramAccount } from './multisig-data-access'
+import {
+  decompileMessage,
+  useMultisigProgram,
+  useMultisigProgramAccount,
+  useTransactionProgramAccount,
+} from './multisig-data-access'
 // 导入工具函数，用于缩短长字符串（如公钥）的显示
 import { ellipsify } from '@/lib/utils'
 // 导入 UI 按钮组件
@@ -56,12 +61,14 @@ import {
 import { Tabs, TabsContent, TabsList, TabsTrigger } from '../ui/tabs'
 
 // 导入 Anchor 框架的类型和工具，用于与 Solana 程序交互
-import { IdlAccounts, BN, Program } from '@coral-xyz/anchor'
+import { IdlAccounts, IdlTypes, BN, Program } from '@coral-xyz/anchor'
 // 导入从 Anchor IDL 生成的多签程序类型
-import { Multisig } from '@project/anchor'
+import { Multisig, PERMISSION_ALL } from '@project/anchor'
 
-// 定义 Transaction 账户的类型，基于 IDL
-type TransactionAccount = IdlAccounts<Multisig>['transaction']
+// 定义还原后的完整指令数据类型
+type InstructionData = IdlTypes<Multisig>['instructionData']
+// 定义白名单条目类型
+type WhitelistEntry = IdlAccounts<Multisig>['programWhitelist']['programs'][number]
 // 定义各种指令的识别码（discriminators），用于解析指令数据
 const IX_DISCRIMINATORS = {
   changeThreshold: Buffer.from([146, 151, 213, 63, 121, 79, 9, 29]),
@@ -72,43 +79,59 @@ const IX_DISCRIMINATORS = {
   pause: Buffer.from([211, 22, 221, 251, 74, 121, 193, 47]),
 }
 
+// 提案状态对应的显示文本
+const PROPOSAL_STATUS_LABELS: Record<string, string> = {
+  draft: '草稿',
+  active: '待处理',
+  approved: '已批准',
+  rejected: '已拒绝',
+  executing: '执行中',
+  executed: '已执行',
+  cancelled: '已取消',
+  expired: '已过期',
+}
+
 // 解析 changeThreshold 指令的辅助函数
-function parseChangeThresholdInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parseChangeThresholdInstruction(instruction: InstructionData, programId: PublicKey) {
   // 检查指令的程序 ID 是否匹配多签程序 ID
   if (!instruction.programId.equals(programId)) return null
   // 将指令数据转换为 Buffer
   const dataBuffer = Buffer.from(instruction.data)
-  // 检查数据长度是否符合预期 (8字节discriminator + 1字节threshold)
-  if (dataBuffer.length !== 9) return null
+  // 检查数据长度是否符合预期 (8字节discriminator + 2字节threshold)
+  if (dataBuffer.length !== 10) return null
 
   // 提取前8字节作为 discriminator
   const discriminator = dataBuffer.subarray(0, 8)
   // 检查 discriminator 是否匹配 changeThreshold 的识别码
   if (!discriminator.equals(IX_DISCRIMINATORS.changeThreshold)) return null
 
-  // 读取第9字节作为新的阈值
-  const newThreshold = dataBuffer.readUInt8(8)
+  // 读取第9-10字节作为新的阈值（小端序 u16）
+  const newThreshold = dataBuffer.readUInt16LE(8)
   // 返回解析结果
   return { type: 'changeThreshold', newThreshold }
 }
 
 // 解析 addOwner 指令的辅助函数
-function parseAddOwnerInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parseAddOwnerInstruction(instruction: InstructionData, programId: PublicKey) {
   if (!instruction.programId.equals(programId)) return null
   const dataBuffer = Buffer.from(instruction.data)
-  // 检查数据长度是否符合预期 (8字节discriminator + 32字节pubkey)
-  if (dataBuffer.length !== 40) return null
+  // 检查数据长度是否符合预期 (8字节discriminator + 32字节pubkey + 1字节权限 + 2字节权重)
+  if (dataBuffer.length !== 43) return null
 
   const discriminator = dataBuffer.slice(0, 8)
   if (!discriminator.equals(IX_DISCRIMINATORS.addOwner)) return null
 
-  // 提取后32字节作为新所有者的公钥
-  const newOwner = new PublicKey(dataBuffer.slice(8))
-  return { type: 'addOwner', newOwner }
+  // 提取32字节作为新所有者的公钥
+  const newOwner = new PublicKey(dataBuffer.slice(8, 40))
+  // 读取1字节作为权限位
+  const permissions = dataBuffer.readUInt8(40)
+  // 读取最后2字节作为投票权重（小端序 u16）
+  const weight = dataBuffer.readUInt16LE(41)
+  return { type: 'addOwner', newOwner, permissions, weight }
 }
 
 // 解析 removeOwner 指令的辅助函数
-function parseRemoveOwnerInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parseRemoveOwnerInstruction(instruction: InstructionData, programId: PublicKey) {
   if (!instruction.programId.equals(programId)) return null
   const dataBuffer = Buffer.from(instruction.data)
   if (dataBuffer.length !== 40) return null
@@ -122,7 +145,7 @@ function parseRemoveOwnerInstruction(instruction: TransactionAccount['instructio
 }
 
 // 主指令解析函数，尝试按顺序解析不同类型的指令
-function parseInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parseInstruction(instruction: InstructionData, programId: PublicKey) {
   // 首先尝试解析为转账指令
   const transferDetails = parseTransferInstruction(instruction)
   if (transferDetails) return { type: 'transfer', ...transferDetails }
@@ -337,7 +360,7 @@ function MultisigCard({ account }: { account: PublicKey }) {
     )
 
   // 检查当前用户是否是此多签的所有者
-  const isOwner = publicKey && multisig?.owners.some((o) => o.equals(publicKey))
+  const isOwner = publicKey && multisig?.owners.some((o) => o.key.equals(publicKey))
 
   // 渲染多签钱包卡片
   return (
@@ -390,13 +413,18 @@ function MultisigCard({ account }: { account: PublicKey }) {
         <div>
           <strong>批准规则:</strong> {multisig.threshold} / {multisig.owners.length}
         </div>
+        {/* 显示配置变更规则 */}
+        <div>
+          <strong>配置变更规则:</strong> {Math.max(multisig.threshold, multisig.configThreshold)} /{' '}
+          {multisig.owners.length}
+        </div>
         {/* 显示所有者列表 */}
         <div>
           <strong>所有者 (Owners):</strong>
           <ul className="list-disc list-inside text-xs space-y-1 mt-1">
             {multisig.owners.map((o) => (
-              <li key={o.toBase58()}>
-                <ExplorerLink path={`account/${o}`} label={ellipsify(o.toBase58())} />
+              <li key={o.key.toBase58()}>
+                <ExplorerLink path={`account/${o.key}`} label={ellipsify(o.key.toBase58())} />
               </li>
             ))}
           </ul>
@@ -406,9 +434,13 @@ function MultisigCard({ account }: { account: PublicKey }) {
           <strong>程序白名单 (Whitelist):</strong>
           {whitelist ? (
             <ul className="list-disc list-inside text-xs space-y-1 mt-1">
-              {whitelist.programs.map((p: PublicKey) => (
-                <li key={p.toBase58()}>
-                  <ExplorerLink path={`account/${p}`} label={ellipsify(p.toBase58())} />
+              {whitelist.programs.map((e: WhitelistEntry) => (
+                <li key={e.programId.toBase58()}>
+                  <ExplorerLink path={`account/${e.programId}`} label={ellipsify(e.programId.toBase58())} />
+                  {e.requiredApprovals > 0 && <span className="ml-1">(阈值 {e.requiredApprovals})</span>}
+                  {e.allowedPrefixes.length > 0 && (
+                    <span className="ml-1">(仅允许 {e.allowedPrefixes.length} 种指令)</span>
+                  )}
                 </li>
               ))}
             </ul>
@@ -586,15 +618,25 @@ function TransactionCard({ transactionAccount }: { transactionAccount: PublicKey
   const multisig = multisigAccountQuery.data
 
   // 检查当前用户是否是所有者
-  const isOwner = publicKey && multisig.owners.some((o) => o.equals(publicKey))
+  const isOwner = publicKey && multisig.owners.some((o) => o.key.equals(publicKey))
   // 检查当前用户是否已批准此交易
   const hasApproved = publicKey && tx.approvals.some((a) => a.equals(publicKey))
+  // 获取提案状态（链上枚举在客户端表示为单键对象）
+  const status = Object.keys(tx.status)[0]
+  // 检查提案是否已执行
+  const isExecuted = status === 'executed'
+  // 检查提案是否仍在投票阶段
+  const isPending = status === 'active' || status === 'approved'
   // 检查是否已达到执行所需的批准阈值
-  const canExecute = tx.approvals.length >= multisig.threshold
+  const canExecute = status === 'approved'
+  // 检查提案是否按指令分步执行
+  const isStepwise = 'stepwise' in tx.executionMode
   // 检查当前用户是否是此交易的提案者
   const isProposer = publicKey && tx.proposer.equals(publicKey)
-  // 解析交易指令的详细信息（只解析第一条指令）
-  const instructionDetails = tx.instructions.length === 1 ? parseInstruction(tx.instructions[0], programId) : null
+  // 解析交易指令的详细信息（只解析第一条指令），先从紧凑消息还原指令；
+  // 引用地址查找表的提案需读取查找表才能还原，此处不解析
+  const instructions = tx.message.addressTableLookups.length === 0 ? decompileMessage(tx.message) : []
+  const instructionDetails = instructions.length === 1 ? parseInstruction(instructions[0], programId) : null
 
   // 渲染交易卡片
   return (
@@ -609,11 +651,11 @@ function TransactionCard({ transactionAccount }: { transactionAccount: PublicKey
         {/* 根据执行状态显示不同的标签 */}
         <div
           className={`flex items-center gap-1 text-xs px-2 py-1 rounded-full ${
-            tx.executed ? 'bg-green-100 text-green-800' : 'bg-orange-100 text-orange-800'
+            isExecuted ? 'bg-green-100 text-green-800' : 'bg-orange-100 text-orange-800'
           }`}
         >
-          {tx.executed ? <CheckCircle size={14} /> : <Hourglass size={14} />}
-          {tx.executed ? '已执行' : '待处理'}
+          {isExecuted ? <CheckCircle size={14} /> : <Hourglass size={14} />}
+          {PROPOSAL_STATUS_LABELS[status] ?? status}
         </div>
       </div>
 
@@ -639,8 +681,8 @@ function TransactionCard({ transactionAccount }: { transactionAccount: PublicKey
         </div>
       </div>
 
-      {/* 如果交易未执行且当前用户是所有者，显示操作按钮 */}
-      {!tx.executed && isOwner && (
+      {/* 如果提案仍在投票阶段且当前用户是所有者，显示操作按钮 */}
+      {isPending && isOwner && (
         <div className="flex flex-wrap gap-2 mt-3">
           {/* 批准/撤销按钮 */}
           {hasApproved ? (
@@ -673,7 +715,7 @@ function TransactionCard({ transactionAccount }: { transactionAccount: PublicKey
             className={!canExecute ? 'bg-gray-200 text-gray-500' : ''}
           >
             <Play size={14} className="mr-1" />
-            {executeMutation.isPending ? '执行中...' : '执行'}
+            {executeMutation.isPending ? '执行中...' : isStepwise ? '分步执行' : '执行'}
           </Button>
 
           {/* 取消提案按钮（仅提案者可见） */}
@@ -694,6 +736,35 @@ function TransactionCard({ transactionAccount }: { transactionAccount: PublicKey
           )}
         </div>
       )}
+
+      {/* 分步执行中的提案，显示继续执行下一条指令的按钮 */}
+      {status === 'executing' && isOwner && (
+        <div className="flex flex-wrap gap-2 mt-3">
+          <Button size="sm" onClick={() => executeMutation.mutate()} disabled={executeMutation.isPending}>
+            <Play size={14} className="mr-1" />
+            {executeMutation.isPending
+              ? '执行中...'
+              : `执行第 ${tx.nextIxIndex + 1} / ${tx.message.instructions.length} 步`}
+          </Button>
+
+          {/* 中止执行按钮（仅提案者可见），取消提案后剩余步骤不再执行 */}
+          {isProposer && (
+            <Button
+              size="sm"
+              variant="destructive"
+              onClick={() => {
+                if (window.confirm('您确定要中止执行吗？已执行的步骤不会回滚，剩余步骤将不再执行。')) {
+                  cancelMutation.mutate()
+                }
+              }}
+              disabled={cancelMutation.isPending}
+            >
+              <XCircle size={14} className="mr-1" />
+              {cancelMutation.isPending ? '中止中...' : '中止执行'}
+            </Button>
+          )}
+        </div>
+      )}
     </div>
   )
 }
@@ -771,7 +842,7 @@ function FundVaultForm({ multisigAccount }: { multisigAccount: PublicKey }) {
 }
 
 // 解析转账指令的辅助函数
-function parseTransferInstruction(instruction: TransactionAccount['instructions'][0]) {
+function parseTransferInstruction(instruction: InstructionData) {
   // 将指令数据转换为 Buffer
   const dataBuffer = Buffer.from(instruction.data)
 
@@ -1064,7 +1135,7 @@ function AddOwnerForm({
       const newOwnerPk = new PublicKey(trimmedOwner)
       // 构建添加所有者的指令
       const instructionPromise = program.methods
-        .addOwner(newOwnerPk)
+        .addOwner(newOwnerPk, PERMISSION_ALL, 1)
         .accounts({ multisig: multisigAccount, vault: vaultPda })
       // 提交提案
       onPropose(instructionPromise)
@@ -1132,8 +1203,8 @@ function RemoveOwnerForm({
     <div className="space-y-4">
       <p className="text-sm">当前 Owners:</p>
       <ul className="text-xs list-disc list-inside">
-        {multisigQuery.data?.owners.map((o: PublicKey) => (
-          <li key={o.toBase58()}>{ellipsify(o.toBase58())}</li>
+        {multisigQuery.data?.owners.map((o) => (
+          <li key={o.key.toBase58()}>{ellipsify(o.key.toBase58())}</li>
         ))}
       </ul>
       <div>
@@ -1235,6 +1306,11 @@ function InstructionDetailsView({ details }: { details: any }) {
                 label={ellipsify(details.programToAdd.toBase58())}
               />
             </p>
+            {details.requiredApprovals > 0 && (
+              <p>
+                风险等级阈值: <strong>{details.requiredApprovals}</strong>
+              </p>
+            )}
           </div>
         </div>
       )
@@ -1276,20 +1352,22 @@ function InstructionDetailsView({ details }: { details: any }) {
 }
 
 // 解析添加到白名单指令的辅助函数
-function parseAddToWhitelistInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parseAddToWhitelistInstruction(instruction: InstructionData, programId: PublicKey) {
   if (!instruction.programId.equals(programId)) return null
   const dataBuffer = Buffer.from(instruction.data)
-  if (dataBuffer.length !== 40) return null
+  // 8字节discriminator + 32字节程序ID + 2字节风险等级阈值
+  if (dataBuffer.length !== 42) return null
 
   const discriminator = dataBuffer.slice(0, 8)
   if (!discriminator.equals(IX_DISCRIMINATORS.addToWhitelist)) return null
 
-  const programToAdd = new PublicKey(dataBuffer.slice(8))
-  return { type: 'addToWhitelist', programToAdd }
+  const programToAdd = new PublicKey(dataBuffer.slice(8, 40))
+  const requiredApprovals = dataBuffer.readUInt16LE(40)
+  return { type: 'addToWhitelist', programToAdd, requiredApprovals }
 }
 
 // 解析从白名单移除指令的辅助函数
-function parseRemoveFromWhitelistInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parseRemoveFromWhitelistInstruction(instruction: InstructionData, programId: PublicKey) {
   if (!instruction.programId.equals(programId)) return null
   const dataBuffer = Buffer.from(instruction.data)
   if (dataBuffer.length !== 40) return null
@@ -1315,6 +1393,8 @@ function AddToWhitelistForm({
 }) {
   // 状态：要添加到白名单的程序ID
   const [programToAdd, setProgramToAdd] = useState('')
+  // 状态：该程序的风险等级阈值，0 表示使用默认批准阈值
+  const [requiredApprovals, setRequiredApprovals] = useState(0)
 
   // 处理表单提交
   const handleSubmit = async () => {
@@ -1323,7 +1403,7 @@ function AddToWhitelistForm({
       // 将字符串公钥转换为 PublicKey 对象
       const programToAddPk = new PublicKey(programToAdd.trim())
       // 构建添加到白名单的指令
-      const instructionPromise = program.methods.addToWhitelist(programToAddPk).accounts({
+      const instructionPromise = program.methods.addToWhitelist(programToAddPk, requiredApprovals).accounts({
         multisig: multisigAccount,
         whitelist: whitelistPda,
         vault: vaultPda,
@@ -1347,6 +1427,15 @@ function AddToWhitelistForm({
           placeholder="粘贴要添加的程序 ID"
         />
       </div>
+      <div>
+        <Label>风险等级阈值 (0 表示使用默认阈值)</Label>
+        <Input
+          type="number"
+          min={0}
+          value={requiredApprovals}
+          onChange={(e) => setRequiredApprovals(parseInt(e.target.value) || 0)}
+        />
+      </div>
       <Button onClick={handleSubmit} disabled={isPending}>
         <ShieldPlus className="h-4 w-4 mr-2" />
         发起添加提案
@@ -1398,6 +1487,7 @@ function RemoveFromWhitelistForm({
       <p className="text-sm text-muted-foreground">当前白名单中的非核心程序:</p>
       <ul className="text-xs list-disc list-inside">
         {whitelistQuery.data?.programs
+          .map((e: WhitelistEntry) => e.programId)
           .filter((p: PublicKey) => !p.equals(SystemProgram.programId) && !p.equals(program.programId))
           .map((p: PublicKey) => (
             <li key={p.toBase58()}>{ellipsify(p.toBase58())}</li>
@@ -1420,7 +1510,7 @@ function RemoveFromWhitelistForm({
 }
 
 // 解析暂停指令的辅助函数
-function parsePauseInstruction(instruction: TransactionAccount['instructions'][0], programId: PublicKey) {
+function parsePauseInstruction(instruction: InstructionData, programId: PublicKey) {
   if (!instruction.programId.equals(programId)) return null
   const dataBuffer = Buffer.from(instruction.data)
   if (dataBuffer.length !== 9) return null